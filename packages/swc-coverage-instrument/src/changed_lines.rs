@@ -0,0 +1,266 @@
+//! 增量插桩：只登记和变更行有交集的语句、函数和分支
+//!
+//! 变更行来自配置中的 `changedLines`（文件 -> 行范围），或由 unified diff（`git diff` 的输出）解析得到
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// 闭区间行范围，配置中写成 `[start, end]` 或单个行号
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl LineRange {
+    pub fn new(start: u32, end: u32) -> Self {
+        Self { start, end }
+    }
+
+    /// 和 [start, end] 行是否有交集
+    pub fn overlaps(&self, start: u32, end: u32) -> bool {
+        self.start <= end && start <= self.end
+    }
+}
+
+impl Serialize for LineRange {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        [self.start, self.end].serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LineRange {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Line(u32),
+            Range([u32; 2]),
+        }
+
+        Ok(match Raw::deserialize(deserializer)? {
+            Raw::Line(line) => Self::new(line, line),
+            Raw::Range([start, end]) => Self::new(start.min(end), start.max(end)),
+        })
+    }
+}
+
+/// 文件 -> 变更行
+pub type ChangedLines = IndexMap<String, Vec<LineRange>>;
+
+/// 解析 `@@ -a,b +c,d @@` 中的 (旧版本行数, 新版本起始行, 新版本行数)，省略的行数为 1
+fn parse_hunk_header(hunk: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = hunk.split_whitespace();
+    let range = |part: &str| -> Option<(u32, u32)> {
+        let (start, count) = part.split_once(',').unwrap_or((part, "1"));
+        Some((start.parse().ok()?, count.parse().ok()?))
+    };
+    let (_, old_count) = range(parts.next()?.strip_prefix('-')?)?;
+    let (new_start, new_count) = range(parts.next()?.strip_prefix('+')?)?;
+    Some((old_count, new_start, new_count))
+}
+
+/// 解析 unified diff，取每个文件新版本中新增 / 修改的行，相邻的行合并成范围；
+/// 删除的文件和只有删除的文件不出现在结果中
+///
+/// 按 `@@` 中的行数判断 hunk 何时结束，hunk 内以 `+++ ` / `--- ` 开头的内容行不会被当成文件头
+pub fn parse_unified_diff(diff: &str) -> ChangedLines {
+    let mut changed = ChangedLines::new();
+    let mut file: Option<String> = None;
+    let mut line = 0u32;
+    // 当前 hunk 中旧版本 / 新版本还剩多少行，都为 0 时处于 hunk 之间
+    let (mut old_remaining, mut new_remaining) = (0u32, 0u32);
+    for text in diff.lines() {
+        if old_remaining == 0 && new_remaining == 0 {
+            if let Some(path) = text.strip_prefix("+++ ") {
+                // 路径后可能跟着制表符和时间戳
+                let path = path.split('\t').next().unwrap_or_default().trim();
+                file = (path != "/dev/null").then(|| path.strip_prefix("b/").unwrap_or(path).to_string());
+            } else if let Some(hunk) = text.strip_prefix("@@ ") {
+                if let Some((old_count, new_start, new_count)) = parse_hunk_header(hunk) {
+                    (old_remaining, line, new_remaining) = (old_count, new_start, new_count);
+                }
+            } else if text.starts_with("diff ") {
+                // 下一个文件，没有 +++ 的文件（纯重命名、二进制文件）不能沿用上一个文件名
+                file = None;
+            }
+            continue;
+        }
+        match text.as_bytes().first() {
+            Some(b'+') => {
+                new_remaining = new_remaining.saturating_sub(1);
+                if let Some(path) = &file {
+                    let ranges = changed.entry(path.clone()).or_default();
+                    match ranges.last_mut() {
+                        Some(last) if last.end + 1 == line => last.end = line,
+                        _ => ranges.push(LineRange::new(line, line)),
+                    }
+                }
+                line += 1;
+            }
+            Some(b'-') => old_remaining = old_remaining.saturating_sub(1),
+            Some(b'\\') => {}
+            // 上下文行，部分工具会去掉空行前面的空格
+            _ => {
+                old_remaining = old_remaining.saturating_sub(1);
+                new_remaining = new_remaining.saturating_sub(1);
+                line += 1;
+            }
+        }
+    }
+    changed
+}
+
+/// 按文件名查找变更行：配置中的路径通常相对仓库根目录，文件名可能是绝对路径，按路径后缀匹配
+pub fn lines_for_file<'a>(changed: &'a ChangedLines, filename: &str) -> Option<&'a [LineRange]> {
+    let filename = filename.replace('\\', "/");
+    changed.iter().find_map(|(path, ranges)| {
+        let path = path.replace('\\', "/");
+        let path = path.trim_start_matches("./");
+        // 按路径分段匹配后缀，`src/a.js` 不会匹配 `/repo/lib-src/a.js`
+        let matched = !path.is_empty() && filename == path
+            || filename
+                .strip_suffix(path)
+                .is_some_and(|prefix| prefix.ends_with('/'));
+        matched.then_some(ranges.as_slice())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(changed: &ChangedLines, file: &str) -> Vec<(u32, u32)> {
+        changed[file].iter().map(|r| (r.start, r.end)).collect()
+    }
+
+    #[test]
+    fn multiple_files() {
+        let diff = "\
+diff --git a/src/a.js b/src/a.js
+index 1111111..2222222 100644
+--- a/src/a.js
++++ b/src/a.js
+@@ -1,3 +1,4 @@
+ const a = 1;
+-const b = 2;
++const b = 3;
++const c = 4;
+ export { a };
+@@ -10,0 +12,1 @@
++console.log(a);
+diff --git a/src/b.js b/src/b.js
+--- a/src/b.js
++++ b/src/b.js
+@@ -5 +5 @@
+-old();
++fresh();
+";
+        let changed = parse_unified_diff(diff);
+        assert_eq!(changed.keys().collect::<Vec<_>>(), ["src/a.js", "src/b.js"]);
+        assert_eq!(ranges(&changed, "src/a.js"), [(2, 3), (12, 12)]);
+        assert_eq!(ranges(&changed, "src/b.js"), [(5, 5)]);
+    }
+
+    #[test]
+    fn new_and_deleted_files() {
+        let diff = "\
+diff --git a/src/new.js b/src/new.js
+new file mode 100644
+--- /dev/null
++++ b/src/new.js
+@@ -0,0 +1,2 @@
++export const x = 1;
++export const y = 2;
+diff --git a/src/gone.js b/src/gone.js
+deleted file mode 100644
+--- a/src/gone.js
++++ /dev/null
+@@ -1,2 +0,0 @@
+-export const x = 1;
+-export const y = 2;
+";
+        let changed = parse_unified_diff(diff);
+        assert_eq!(changed.keys().collect::<Vec<_>>(), ["src/new.js"]);
+        assert_eq!(ranges(&changed, "src/new.js"), [(1, 2)]);
+    }
+
+    #[test]
+    fn renames() {
+        let diff = "\
+diff --git a/src/old.js b/src/renamed.js
+similarity index 90%
+rename from src/old.js
+rename to src/renamed.js
+--- a/src/old.js
++++ b/src/renamed.js
+@@ -2 +2 @@
+-let v = 1;
++let v = 2;
+diff --git a/src/moved.js b/src/lib/moved.js
+similarity index 100%
+rename from src/moved.js
+rename to src/lib/moved.js
+diff --git a/logo.png b/logo.png
+Binary files a/logo.png and b/logo.png differ
+";
+        let changed = parse_unified_diff(diff);
+        assert_eq!(changed.keys().collect::<Vec<_>>(), ["src/renamed.js"]);
+        assert_eq!(ranges(&changed, "src/renamed.js"), [(2, 2)]);
+    }
+
+    #[test]
+    fn header_like_lines_inside_hunks() {
+        // 新增的 `++ x` 行和删除的 `-- y` 行在 diff 中分别是 `+++ x` 和 `--- y`
+        let diff = "\
+--- a/src/a.js
++++ b/src/a.js
+@@ -1,3 +1,3 @@
+ let x = 0;
+--- y;
++++ x;
+
+@@ -8,1 +8,2 @@
+ x;
++++ x;
+";
+        let changed = parse_unified_diff(diff);
+        assert_eq!(changed.keys().collect::<Vec<_>>(), ["src/a.js"]);
+        assert_eq!(ranges(&changed, "src/a.js"), [(2, 2), (9, 9)]);
+    }
+
+    #[test]
+    fn timestamps_and_no_prefix() {
+        let diff = "\
+--- src/a.js\t2024-01-01 00:00:00
++++ src/a.js\t2024-01-02 00:00:00
+@@ -1 +1,2 @@
+ a();
++b();
+\\ No newline at end of file
+";
+        assert_eq!(ranges(&parse_unified_diff(diff), "src/a.js"), [(2, 2)]);
+    }
+
+    #[test]
+    fn lookup_by_path_suffix() {
+        let mut changed = ChangedLines::new();
+        changed.insert("./src/a.js".into(), vec![LineRange::new(1, 1)]);
+        changed.insert("lib\\b.js".into(), vec![LineRange::new(2, 2)]);
+        changed.insert(String::new(), vec![LineRange::new(3, 3)]);
+        assert!(lines_for_file(&changed, "src/a.js").is_some());
+        assert!(lines_for_file(&changed, "/repo/src/a.js").is_some());
+        assert!(lines_for_file(&changed, "C:\\repo\\lib\\b.js").is_some());
+        assert!(lines_for_file(&changed, "/repo/other-src/a.js").is_none());
+        assert!(lines_for_file(&changed, "/repo/xsrc/a.js").is_none());
+        assert!(lines_for_file(&changed, "/repo/src/c.js").is_none());
+    }
+
+    #[test]
+    fn line_range_from_json() {
+        let changed: ChangedLines = serde_json::from_str(r#"{ "src/a.js": [[10, 24], 31, [5, 3]] }"#).unwrap();
+        assert_eq!(ranges(&changed, "src/a.js"), [(10, 24), (31, 31), (3, 5)]);
+        assert!(changed["src/a.js"][0].overlaps(24, 30));
+        assert!(!changed["src/a.js"][0].overlaps(25, 30));
+    }
+}
@@ -0,0 +1,255 @@
+//! 从 CI 环境变量中读取构建信息，填充 Canyon 元数据
+//!
+//! swc 插件运行在 wasm 沙箱中，`TransformPluginMetadataContextKind::Env` 只有 envName，
+//! 环境变量需要在 .swcrc 的 `jsc.experimental.pluginEnvVars` 中声明后经 experimental context 传入，
+//! 读取方式由调用方通过 `lookup` 提供。
+
+use indexmap::IndexMap;
+
+use crate::options::CanyonOptions;
+
+/// 一个 CI 平台的内置变量映射，每个字段按顺序取第一个有值的变量
+struct Provider {
+    name: &'static str,
+    detect: &'static str,
+    project_id: &'static [&'static str],
+    sha: &'static [&'static str],
+    branch: &'static [&'static str],
+    build_id: &'static [&'static str],
+    compare_target: &'static [&'static str],
+}
+
+const PROVIDERS: &[Provider] = &[
+    Provider {
+        name: "github",
+        detect: "GITHUB_ACTIONS",
+        project_id: &["GITHUB_REPOSITORY_ID"],
+        sha: &["GITHUB_SHA"],
+        branch: &["GITHUB_HEAD_REF", "GITHUB_REF_NAME"],
+        build_id: &["GITHUB_RUN_ID"],
+        compare_target: &["GITHUB_BASE_REF"],
+    },
+    Provider {
+        name: "gitlab",
+        detect: "GITLAB_CI",
+        project_id: &["CI_PROJECT_ID"],
+        sha: &["CI_COMMIT_SHA"],
+        branch: &["CI_MERGE_REQUEST_SOURCE_BRANCH_NAME", "CI_COMMIT_BRANCH", "CI_COMMIT_REF_NAME"],
+        build_id: &["CI_MERGE_REQUEST_IID", "CI_JOB_ID"],
+        compare_target: &["CI_MERGE_REQUEST_TARGET_BRANCH_NAME"],
+    },
+    Provider {
+        name: "azure",
+        detect: "TF_BUILD",
+        project_id: &["SYSTEM_TEAMPROJECTID"],
+        sha: &["BUILD_SOURCEVERSION"],
+        branch: &["SYSTEM_PULLREQUEST_SOURCEBRANCH", "BUILD_SOURCEBRANCHNAME"],
+        build_id: &["BUILD_BUILDID"],
+        compare_target: &["SYSTEM_PULLREQUEST_TARGETBRANCH"],
+    },
+];
+
+fn first_of<'a>(names: impl IntoIterator<Item = &'a str>, lookup: &impl Fn(&str) -> Option<String>) -> Option<String> {
+    names
+        .into_iter()
+        .filter_map(lookup)
+        .find(|value| !value.is_empty())
+}
+
+impl CanyonOptions {
+    /// 用环境变量补全未显式配置的字段：显式配置 > envMapping > 内置 CI 映射，
+    /// instrumentCwd 没有内置映射时取 swc 的 cwd
+    pub fn fill_from_env(
+        &mut self,
+        cwd: &str,
+        env_mapping: &IndexMap<String, Vec<String>>,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) {
+        let provider = PROVIDERS
+            .iter()
+            .find(|p| lookup(p.detect).is_some_and(|v| !v.is_empty()));
+
+        let fields = [
+            ("projectID", &mut self.project_id, provider.map(|p| p.project_id)),
+            ("sha", &mut self.sha, provider.map(|p| p.sha)),
+            ("branch", &mut self.branch, provider.map(|p| p.branch)),
+            ("reporter", &mut self.reporter, None),
+            ("dsn", &mut self.dsn, None),
+            ("instrumentCwd", &mut self.instrument_cwd, None),
+            ("buildID", &mut self.build_id, provider.map(|p| p.build_id)),
+            ("compareTarget", &mut self.compare_target, provider.map(|p| p.compare_target)),
+        ];
+        for (key, field, builtin) in fields {
+            if field.is_some() {
+                continue;
+            }
+            let mapped = env_mapping
+                .get(key)
+                .and_then(|names| first_of(names.iter().map(String::as_str), &lookup));
+            *field = mapped.or_else(|| builtin.and_then(|names| first_of(names.iter().copied(), &lookup)));
+        }
+
+        if self.instrument_cwd.is_none() {
+            self.instrument_cwd = Some(cwd.to_string());
+        }
+        if self.build_provider.is_none() {
+            self.build_provider = env_mapping
+                .get("buildProvider")
+                .and_then(|names| first_of(names.iter().map(String::as_str), &lookup))
+                .or_else(|| provider.map(|p| p.name.to_string()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn fill(options: &mut CanyonOptions, mapping: &[(&str, &[&str])], env: &[(&str, &str)]) {
+        let env_mapping = mapping
+            .iter()
+            .map(|(key, names)| (key.to_string(), names.iter().map(|n| n.to_string()).collect()))
+            .collect();
+        let env: HashMap<&str, &str> = env.iter().copied().collect();
+        options.fill_from_env("/work/app", &env_mapping, |name| env.get(name).map(|v| v.to_string()));
+    }
+
+    #[test]
+    fn github_prefers_head_ref_for_pull_requests() {
+        let mut options = CanyonOptions::default();
+        fill(
+            &mut options,
+            &[],
+            &[
+                ("GITHUB_ACTIONS", "true"),
+                ("GITHUB_REPOSITORY_ID", "42"),
+                ("GITHUB_SHA", "abc"),
+                ("GITHUB_HEAD_REF", "feature"),
+                ("GITHUB_REF_NAME", "12/merge"),
+                ("GITHUB_RUN_ID", "7"),
+                ("GITHUB_BASE_REF", "main"),
+            ],
+        );
+        assert_eq!(options.project_id.as_deref(), Some("42"));
+        assert_eq!(options.sha.as_deref(), Some("abc"));
+        assert_eq!(options.branch.as_deref(), Some("feature"));
+        assert_eq!(options.build_id.as_deref(), Some("7"));
+        assert_eq!(options.compare_target.as_deref(), Some("main"));
+        assert_eq!(options.build_provider.as_deref(), Some("github"));
+    }
+
+    #[test]
+    fn github_push_falls_back_to_ref_name() {
+        let mut options = CanyonOptions::default();
+        fill(
+            &mut options,
+            &[],
+            &[("GITHUB_ACTIONS", "true"), ("GITHUB_HEAD_REF", ""), ("GITHUB_REF_NAME", "main")],
+        );
+        assert_eq!(options.branch.as_deref(), Some("main"));
+        assert_eq!(options.compare_target, None);
+    }
+
+    #[test]
+    fn gitlab_merge_request() {
+        let mut options = CanyonOptions::default();
+        fill(
+            &mut options,
+            &[],
+            &[
+                ("GITLAB_CI", "true"),
+                ("CI_PROJECT_ID", "9"),
+                ("CI_COMMIT_SHA", "def"),
+                ("CI_MERGE_REQUEST_SOURCE_BRANCH_NAME", "fix"),
+                ("CI_COMMIT_REF_NAME", "fix-ref"),
+                ("CI_MERGE_REQUEST_IID", "15"),
+                ("CI_JOB_ID", "300"),
+                ("CI_MERGE_REQUEST_TARGET_BRANCH_NAME", "develop"),
+            ],
+        );
+        assert_eq!(options.project_id.as_deref(), Some("9"));
+        assert_eq!(options.sha.as_deref(), Some("def"));
+        assert_eq!(options.branch.as_deref(), Some("fix"));
+        assert_eq!(options.build_id.as_deref(), Some("15"));
+        assert_eq!(options.compare_target.as_deref(), Some("develop"));
+        assert_eq!(options.build_provider.as_deref(), Some("gitlab"));
+    }
+
+    #[test]
+    fn azure_pipeline() {
+        let mut options = CanyonOptions::default();
+        fill(
+            &mut options,
+            &[],
+            &[
+                ("TF_BUILD", "True"),
+                ("SYSTEM_TEAMPROJECTID", "p1"),
+                ("BUILD_SOURCEVERSION", "123"),
+                ("BUILD_SOURCEBRANCHNAME", "main"),
+                ("BUILD_BUILDID", "88"),
+            ],
+        );
+        assert_eq!(options.project_id.as_deref(), Some("p1"));
+        assert_eq!(options.sha.as_deref(), Some("123"));
+        assert_eq!(options.branch.as_deref(), Some("main"));
+        assert_eq!(options.build_id.as_deref(), Some("88"));
+        assert_eq!(options.build_provider.as_deref(), Some("azure"));
+    }
+
+    #[test]
+    fn explicit_options_win_over_env_mapping_and_provider() {
+        let mut options = CanyonOptions {
+            sha: Some("explicit".into()),
+            instrument_cwd: Some("/explicit".into()),
+            build_provider: Some("jenkins".into()),
+            ..Default::default()
+        };
+        fill(
+            &mut options,
+            &[("sha", &["CANYON_SHA"]), ("branch", &["CANYON_BRANCH"]), ("instrumentCwd", &["CANYON_CWD"])],
+            &[
+                ("GITHUB_ACTIONS", "true"),
+                ("GITHUB_SHA", "from-github"),
+                ("GITHUB_REF_NAME", "from-github"),
+                ("CANYON_SHA", "from-mapping"),
+                ("CANYON_BRANCH", "from-mapping"),
+                ("CANYON_CWD", "/from-mapping"),
+            ],
+        );
+        assert_eq!(options.sha.as_deref(), Some("explicit"));
+        assert_eq!(options.branch.as_deref(), Some("from-mapping"));
+        assert_eq!(options.instrument_cwd.as_deref(), Some("/explicit"));
+        assert_eq!(options.build_provider.as_deref(), Some("jenkins"));
+    }
+
+    #[test]
+    fn env_mapping_takes_first_non_empty_variable() {
+        let mut options = CanyonOptions::default();
+        fill(
+            &mut options,
+            &[("dsn", &["CANYON_DSN", "FALLBACK_DSN"]), ("buildProvider", &["CANYON_PROVIDER"])],
+            &[("CANYON_DSN", ""), ("FALLBACK_DSN", "http://collector"), ("CANYON_PROVIDER", "drone")],
+        );
+        assert_eq!(options.dsn.as_deref(), Some("http://collector"));
+        assert_eq!(options.build_provider.as_deref(), Some("drone"));
+    }
+
+    #[test]
+    fn missing_variables_leave_fields_unset() {
+        let mut options = CanyonOptions::default();
+        fill(&mut options, &[("sha", &["CANYON_SHA"])], &[("GITHUB_ACTIONS", "")]);
+        assert_eq!(
+            options,
+            CanyonOptions { instrument_cwd: Some("/work/app".into()), ..Default::default() }
+        );
+    }
+
+    #[test]
+    fn instrument_cwd_from_mapping_before_swc_cwd() {
+        let mut options = CanyonOptions::default();
+        fill(&mut options, &[("instrumentCwd", &["CANYON_CWD"])], &[("CANYON_CWD", "/repo")]);
+        assert_eq!(options.instrument_cwd.as_deref(), Some("/repo"));
+    }
+}
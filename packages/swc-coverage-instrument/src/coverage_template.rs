@@ -4,9 +4,11 @@ use std::hash::{Hash, Hasher};
 use swc_core::{
     common::{util::take::Take, DUMMY_SP},
     ecma::{ast::*, utils::quote_ident},
+    quote,
 };
 
-use crate::source_coverage::{Range, SourceCoverage};
+use crate::options::{HitMode, InstrumentOptions};
+use crate::source_coverage::{BranchMapping, FunctionMapping, Range, SourceCoverage};
 
 /// 创建 Range 对象字面量: { start: { line, column }, end: { line, column } }
 fn create_range_object_lit(range: &Range) -> Expr {
@@ -65,8 +67,97 @@ fn create_range_object_lit(range: &Range) -> Expr {
     })
 }
 
+/// 创建 fnMap 中的一项: { name, decl, loc, line }
+fn create_fn_mapping_object_lit(mapping: &FunctionMapping) -> Expr {
+    Expr::Object(ObjectLit {
+        span: DUMMY_SP,
+        props: vec![
+            create_str_prop("name", &mapping.name),
+            create_expr_prop("decl", create_range_object_lit(&mapping.decl)),
+            create_expr_prop("loc", create_range_object_lit(&mapping.loc)),
+            create_expr_prop("line", create_num_lit(mapping.line)),
+        ],
+    })
+}
+
+/// 创建 branchMap 中的一项: { loc, type, locations, line }
+fn create_branch_mapping_object_lit(mapping: &BranchMapping) -> Expr {
+    Expr::Object(ObjectLit {
+        span: DUMMY_SP,
+        props: vec![
+            create_expr_prop("loc", create_range_object_lit(&mapping.loc)),
+            create_str_prop("type", &mapping.branch_type),
+            create_expr_prop(
+                "locations",
+                create_array_lit(mapping.locations.iter().map(create_range_object_lit).collect()),
+            ),
+            create_expr_prop("line", create_num_lit(mapping.line)),
+        ],
+    })
+}
+
+fn create_array_lit(elems: Vec<Expr>) -> Expr {
+    Expr::Array(ArrayLit {
+        span: DUMMY_SP,
+        elems: elems
+            .into_iter()
+            .map(|expr| {
+                Some(ExprOrSpread {
+                    spread: None,
+                    expr: Box::new(expr),
+                })
+            })
+            .collect(),
+    })
+}
+
+/// 创建 key: value 属性
+fn create_expr_prop(key: &str, value: Expr) -> PropOrSpread {
+    PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+        key: PropName::Ident(Ident::new(key.into(), DUMMY_SP, Default::default()).into()),
+        value: Box::new(value),
+    })))
+}
+
+/// 创建 "key": value 属性，用于 statementMap / s 等以 id 为 key 的对象
+fn create_id_prop(id: u32, value: Expr) -> PropOrSpread {
+    PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+        key: PropName::Str(Str {
+            value: id.to_string().into(),
+            span: DUMMY_SP,
+            raw: None,
+        }),
+        value: Box::new(value),
+    })))
+}
+
+fn create_num_lit(value: u32) -> Expr {
+    Expr::Lit(Lit::Num(Number {
+        span: DUMMY_SP,
+        value: value as f64,
+        raw: None,
+    }))
+}
+
+/// 创建 key: "value" 属性
+fn create_str_prop(key: &str, value: &str) -> PropOrSpread {
+    PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+        key: PropName::Ident(Ident::new(key.into(), DUMMY_SP, Default::default()).into()),
+        value: Box::new(Expr::Lit(Lit::Str(Str {
+            value: value.into(),
+            span: DUMMY_SP,
+            raw: None,
+        }))),
+    })))
+}
+
 /// 创建覆盖率数据对象
-fn create_coverage_data_object(filename: &str, cov: &SourceCoverage, ast_json: Option<&str>) -> Expr {
+fn create_coverage_data_object(
+    filename: &str,
+    cov: &SourceCoverage,
+    ast_json: Option<&str>,
+    options: &InstrumentOptions,
+) -> Expr {
     // statementMap: { "0": { start, end }, ... }
     let statement_map_props: Vec<PropOrSpread> = cov
         .statement_map
@@ -103,13 +194,41 @@ fn create_coverage_data_object(filename: &str, cov: &SourceCoverage, ast_json: O
         })
         .collect();
 
+    // fnMap: { "0": { name, decl, loc, line }, ... }
+    let fn_map_props: Vec<PropOrSpread> = cov
+        .fn_map
+        .iter()
+        .map(|(k, v)| create_id_prop(*k, create_fn_mapping_object_lit(v)))
+        .collect();
+
+    // f: { "0": 0, ... }
+    let f_props: Vec<PropOrSpread> = cov
+        .f
+        .iter()
+        .map(|(k, v)| create_id_prop(*k, create_num_lit(*v)))
+        .collect();
+
+    // branchMap: { "0": { loc, type, locations, line }, ... }
+    let branch_map_props: Vec<PropOrSpread> = cov
+        .branch_map
+        .iter()
+        .map(|(k, v)| create_id_prop(*k, create_branch_mapping_object_lit(v)))
+        .collect();
+
+    // b: { "0": [0, 0], ... }
+    let b_props: Vec<PropOrSpread> = cov
+        .b
+        .iter()
+        .map(|(k, v)| create_id_prop(*k, create_array_lit(v.iter().map(|n| create_num_lit(*n)).collect())))
+        .collect();
+
     let mut props = vec![
         PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
             key: PropName::Ident(Ident::new("path".into(), DUMMY_SP, Default::default()).into()),
             value: Box::new(Expr::Lit(Lit::Str(Str {
                 value: filename.into(),
                 span: DUMMY_SP,
-                raw: Some(format!(r#""{}""#, filename).into()),
+                raw: Some(format!(r#""{filename}""#).into()),
             }))),
         }))),
         PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
@@ -123,14 +242,14 @@ fn create_coverage_data_object(filename: &str, cov: &SourceCoverage, ast_json: O
             key: PropName::Ident(Ident::new("fnMap".into(), DUMMY_SP, Default::default()).into()),
             value: Box::new(Expr::Object(ObjectLit {
                 span: DUMMY_SP,
-                props: vec![],
+                props: fn_map_props,
             })),
         }))),
         PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
             key: PropName::Ident(Ident::new("branchMap".into(), DUMMY_SP, Default::default()).into()),
             value: Box::new(Expr::Object(ObjectLit {
                 span: DUMMY_SP,
-                props: vec![],
+                props: branch_map_props,
             })),
         }))),
         PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
@@ -144,18 +263,25 @@ fn create_coverage_data_object(filename: &str, cov: &SourceCoverage, ast_json: O
             key: PropName::Ident(Ident::new("f".into(), DUMMY_SP, Default::default()).into()),
             value: Box::new(Expr::Object(ObjectLit {
                 span: DUMMY_SP,
-                props: vec![],
+                props: f_props,
             })),
         }))),
         PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
             key: PropName::Ident(Ident::new("b".into(), DUMMY_SP, Default::default()).into()),
             value: Box::new(Expr::Object(ObjectLit {
                 span: DUMMY_SP,
-                props: vec![],
+                props: b_props,
             })),
         }))),
     ];
 
+    props.push(create_str_prop(COVERAGE_SCHEMA_KEY, COVERAGE_SCHEMA));
+
+    // Canyon 元数据：projectID、sha、branch 等，只写入已配置的字段
+    for (key, value) in options.canyon.fields() {
+        props.push(create_str_prop(key, value));
+    }
+
     // 添加 ast 字段（如果有的话）
     if let Some(ast_str) = ast_json {
         props.push(PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
@@ -187,27 +313,98 @@ fn create_coverage_data_object(filename: &str, cov: &SourceCoverage, ast_json: O
     })
 }
 
+/// 创建 cov_xxx().<key> 成员表达式
+fn create_cov_member_expr(cov_fn_ident: &Ident, key: &str) -> Expr {
+    Expr::Member(MemberExpr {
+        span: DUMMY_SP,
+        obj: Box::new(Expr::Call(CallExpr {
+            span: DUMMY_SP,
+            ctxt: Default::default(),
+            callee: Callee::Expr(Box::new(Expr::Ident(cov_fn_ident.clone()))),
+            args: vec![],
+            type_args: None,
+        })),
+        prop: MemberProp::Ident(Ident::new(key.into(), DUMMY_SP, Default::default()).into()),
+    })
+}
+
+/// 创建 obj[index] 成员表达式
+fn create_index_member_expr(obj: Expr, index: u32) -> MemberExpr {
+    MemberExpr {
+        span: DUMMY_SP,
+        obj: Box::new(obj),
+        prop: MemberProp::Computed(ComputedPropName {
+            span: DUMMY_SP,
+            expr: Box::new(Expr::Lit(Lit::Num(Number {
+                span: DUMMY_SP,
+                value: index as f64,
+                raw: None,
+            }))),
+        }),
+    }
+}
+
+/// 创建计数表达式：count 模式为 target++，boolean 模式为 target = 1
+fn create_update_expr(target: MemberExpr, hit_mode: HitMode) -> Expr {
+    match hit_mode {
+        HitMode::Count => Expr::Update(UpdateExpr {
+            span: DUMMY_SP,
+            op: UpdateOp::PlusPlus,
+            prefix: false,
+            arg: Box::new(Expr::Member(target)),
+        }),
+        HitMode::Boolean => Expr::Assign(AssignExpr {
+            span: DUMMY_SP,
+            op: AssignOp::Assign,
+            left: AssignTarget::Simple(SimpleAssignTarget::Member(target)),
+            right: Box::new(create_num_lit(1)),
+        }),
+    }
+}
+
+/// 创建语句计数器自增表达式: cov_xxx().s[id]++
+pub fn create_increase_counter_expr(id: u32, cov_fn_ident: &Ident, hit_mode: HitMode) -> Expr {
+    create_update_expr(create_index_member_expr(create_cov_member_expr(cov_fn_ident, "s"), id), hit_mode)
+}
+
+/// 创建函数计数器自增表达式: cov_xxx().f[id]++
+pub fn create_increase_fn_counter_expr(id: u32, cov_fn_ident: &Ident, hit_mode: HitMode) -> Expr {
+    create_update_expr(create_index_member_expr(create_cov_member_expr(cov_fn_ident, "f"), id), hit_mode)
+}
+
+/// 创建分支计数器自增表达式: cov_xxx().b[id][index]++
+pub fn create_increase_branch_counter_expr(id: u32, index: u32, cov_fn_ident: &Ident, hit_mode: HitMode) -> Expr {
+    create_update_expr(
+        create_index_member_expr(
+            Expr::Member(create_index_member_expr(create_cov_member_expr(cov_fn_ident, "b"), id)),
+            index,
+        ),
+        hit_mode,
+    )
+}
+
 /// 创建 var ident = value; 语句
 fn create_assignment_stmt(ident: &Ident, value: Expr) -> Stmt {
     Stmt::Decl(Decl::Var(Box::new(VarDecl {
         kind: VarDeclKind::Var,
         decls: vec![VarDeclarator {
             span: DUMMY_SP,
-            name: Pat::Assign(AssignPat {
-                span: DUMMY_SP,
-                left: Box::new(Pat::Ident(BindingIdent::from(ident.clone()))),
-                right: Box::new(value),
-            }),
-            init: None,
+            name: Pat::Ident(BindingIdent::from(ident.clone())),
+            init: Some(Box::new(value)),
             definite: false,
         }],
         ..VarDecl::dummy()
     })))
 }
 
+/// 覆盖率数据中的 schema 标记，和 istanbul-lib-instrument 一致，
+/// 同时用来识别已插桩的文件（函数名被压缩改掉后依然能识别）
+pub const COVERAGE_SCHEMA_KEY: &str = "_coverageSchema";
+pub const COVERAGE_SCHEMA: &str = "1a1c01bbd47fc00a2c39e90264f33305004495a9";
+
 /// 计算覆盖率数据的 hash
 fn compute_hash(_filename: &str, cov: &SourceCoverage) -> String {
-    let coverage_str = format!("{:?}", cov);
+    let coverage_str = format!("{cov:?}");
     let mut hasher = DefaultHasher::new();
     coverage_str.hash(&mut hasher);
     hasher.finish().to_string()
@@ -235,32 +432,33 @@ fn create_coverage_fn_decl(
     cov_fn_ident: &Ident,
     cov: &SourceCoverage,
     ast_json: Option<&str>,
+    options: &InstrumentOptions,
 ) -> Stmt {
     println!("  === create_coverage_fn_decl ===");
     let mut stmts = vec![];
 
     // 1. var path = "src/file.js";
-    println!("    [1] 创建 var path = \"{}\"", filename);
+    println!("    [1] 创建 var path = \"{filename}\"");
     let ident_path = Ident::new("path".into(), DUMMY_SP, Default::default());
     stmts.push(create_assignment_stmt(
         &ident_path,
         Expr::Lit(Lit::Str(Str {
             value: filename.into(),
             span: DUMMY_SP,
-            raw: Some(format!(r#""{}""#, filename).into()),
+            raw: Some(format!(r#""{filename}""#).into()),
         })),
     ));
 
     // 2. var hash = "...";
     let hash = compute_hash(filename, cov);
-    println!("    [2] 创建 var hash = \"{}\"", hash);
+    println!("    [2] 创建 var hash = \"{hash}\"");
     let ident_hash = Ident::new("hash".into(), DUMMY_SP, Default::default());
     stmts.push(create_assignment_stmt(
         &ident_hash,
         Expr::Lit(Lit::Str(Str {
             value: hash.clone().into(),
             span: DUMMY_SP,
-            raw: Some(format!(r#""{}""#, hash).into()),
+            raw: Some(format!(r#""{hash}""#).into()),
         })),
     ));
 
@@ -312,7 +510,7 @@ fn create_coverage_fn_decl(
     let ident_coverage_data = Ident::new("coverageData".into(), DUMMY_SP, Default::default());
     stmts.push(create_assignment_stmt(
         &ident_coverage_data,
-        create_coverage_data_object(filename, cov, ast_json),
+        create_coverage_data_object(filename, cov, ast_json, options),
     ));
 
     // 6. var coverage = global[gcv] || (global[gcv] = {});
@@ -497,25 +695,98 @@ fn create_coverage_fn_decl(
     }))
 }
 
+/// 创建浏览器上报运行时，同一个全局对象上只生效一次：
+/// 定时、visibilitychange（隐藏时）和 beforeunload 时把 `{ ...Canyon 元数据, sessionId, coverage }` POST 到 dsn，
+/// 优先使用 navigator.sendBeacon，失败时退回 fetch；`__canyon_upload__()` 可手动触发一次上报。
+/// coverage 是页面加载以来的累计计数，收集端按 sessionId 用最新一次上报替换旧的，不会重复累加
+fn create_uploader_stmt(options: &InstrumentOptions) -> Option<Stmt> {
+    let upload = options.upload.as_ref()?;
+    let dsn = options.canyon.dsn.as_deref()?;
+
+    let meta = Expr::Object(ObjectLit {
+        span: DUMMY_SP,
+        props: options
+            .canyon
+            .fields()
+            .into_iter()
+            .map(|(key, value)| create_str_prop(key, value))
+            .collect(),
+    });
+    let dsn = Expr::Lit(Lit::Str(Str {
+        value: dsn.into(),
+        span: DUMMY_SP,
+        raw: None,
+    }));
+    let interval = Expr::Lit(Lit::Num(Number {
+        span: DUMMY_SP,
+        value: upload.interval as f64,
+        raw: None,
+    }));
+
+    Some(quote!(
+        r#"(function (global) {
+            if (global.__canyon_upload__) return;
+            var dsn = $dsn;
+            var meta = $meta;
+            var sessionId = Date.now().toString(36) + "-" + Math.random().toString(36).slice(2);
+            function upload() {
+                var coverage = global.__coverage__;
+                if (!coverage) return;
+                var payload = {};
+                for (var key in meta) payload[key] = meta[key];
+                payload.sessionId = sessionId;
+                payload.coverage = coverage;
+                var body = JSON.stringify(payload);
+                try {
+                    if (typeof navigator !== "undefined" && navigator.sendBeacon && navigator.sendBeacon(dsn, body)) return;
+                    if (typeof fetch === "function") {
+                        fetch(dsn, {
+                            method: "POST",
+                            headers: { "Content-Type": "application/json" },
+                            body: body,
+                            keepalive: body.length < 60000
+                        }).catch(function () {});
+                    }
+                } catch (e) {}
+            }
+            global.__canyon_upload__ = upload;
+            var timer = setInterval(upload, $interval);
+            if (timer && timer.unref) timer.unref();
+            if (typeof document !== "undefined" && document.addEventListener) {
+                document.addEventListener("visibilitychange", function () {
+                    if (document.visibilityState === "hidden") upload();
+                });
+            }
+            if (typeof window !== "undefined" && window.addEventListener) {
+                window.addEventListener("beforeunload", upload);
+            }
+        })(new Function("return this")());"# as Stmt,
+        dsn: Expr = dsn,
+        meta: Expr = meta,
+        interval: Expr = interval,
+    ))
+}
+
 /// 创建覆盖率初始化语句
-/// 返回: [函数声明, 调用语句]
+/// 返回: [函数声明, 调用语句, 上报运行时（配置了 upload 时）]
 pub fn create_coverage_init_stmts(
     filename: &str,
     cov_fn_ident: &Ident,
     cov: &SourceCoverage,
     ast_json: Option<&str>,
+    options: &InstrumentOptions,
 ) -> Vec<Stmt> {
     println!("=== create_coverage_init_stmts ===");
-    println!("  filename: {}", filename);
+    println!("  filename: {filename}");
     println!("  cov_fn_ident: {:?}", cov_fn_ident.sym);
     println!("  语句数量: {}", cov.statement_map.len());
     if let Some(json) = ast_json {
         println!("  AST JSON 长度: {} 字节", json.len());
     }
     
-    let stmts = vec![
+    let mut stmts = vec![
         // function cov_xxx() { ... }
-        create_coverage_fn_decl(filename, cov_fn_ident, cov, ast_json),
+        create_coverage_fn_decl(filename, cov_fn_ident, cov, ast_json, options),
         // cov_xxx();
         Stmt::Expr(ExprStmt {
             span: DUMMY_SP,
@@ -528,7 +799,10 @@ pub fn create_coverage_init_stmts(
             })),
         }),
     ];
-    
+    if let Some(uploader) = create_uploader_stmt(options) {
+        stmts.push(uploader);
+    }
+
     println!("  生成了 {} 个初始化语句", stmts.len());
     stmts
 }
@@ -0,0 +1,154 @@
+//! 单文件插桩上限：体积很大的第三方文件插桩后，代码和内嵌的覆盖率对象都会膨胀，超过上限的文件不插桩
+
+use swc_core::ecma::{
+    ast::*,
+    visit::{Visit, VisitWith},
+};
+
+use crate::options::InstrumentOptions;
+
+/// 没有配置 maxLineLength 时的默认规则：大于 MINIFIED_MIN_SIZE 字节且平均每行超过该字节数的文件视为压缩代码
+const MINIFIED_AVG_LINE_LENGTH: usize = 500;
+/// 小于该字节数的文件不按平均行长判断，避免把单行的小文件当成压缩代码
+const MINIFIED_MIN_SIZE: usize = 4096;
+
+/// 统计语句数，和 statementMap 的规模大致相当
+#[derive(Default)]
+struct StatementCounter {
+    count: usize,
+}
+
+impl Visit for StatementCounter {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        self.count += 1;
+        stmt.visit_children_with(self);
+    }
+}
+
+/// 检查 maxSourceSize / maxLineLength / maxStatements，超过时返回原因；
+/// 没有配置 maxLineLength 时按平均行长识别压缩代码，配置后以最长行为准；source 读取不到时跳过前两项
+pub fn exceeded_file_limit(
+    options: &InstrumentOptions,
+    program: &Program,
+    source: impl FnOnce() -> Option<String>,
+) -> Option<String> {
+    if let Some(source) = source() {
+        if let Some(max) = options.max_source_size {
+            if source.len() > max {
+                return Some(format!("源码 {} 字节，超过 maxSourceSize {max}", source.len()));
+            }
+        }
+        if let Some(max) = options.max_line_length {
+            let long_line = source
+                .lines()
+                .enumerate()
+                .map(|(index, line)| (index + 1, line.chars().count()))
+                .find(|(_, len)| *len > max);
+            if let Some((line, len)) = long_line {
+                return Some(format!("第 {line} 行 {len} 个字符，超过 maxLineLength {max}，疑似压缩代码"));
+            }
+        } else if source.len() >= MINIFIED_MIN_SIZE {
+            let average = source.len() / source.lines().count().max(1);
+            if average > MINIFIED_AVG_LINE_LENGTH {
+                return Some(format!("平均每行 {average} 字节，疑似压缩代码，可配置 maxLineLength 改用最长行判断"));
+            }
+        }
+    }
+    if let Some(max) = options.max_statements {
+        let mut counter = StatementCounter::default();
+        program.visit_with(&mut counter);
+        if counter.count > max {
+            return Some(format!("{} 条语句，超过 maxStatements {max}", counter.count));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::common::DUMMY_SP;
+
+    use super::*;
+
+    fn program(statements: usize) -> Program {
+        Program::Script(Script {
+            span: DUMMY_SP,
+            body: (0..statements).map(|_| Stmt::Empty(EmptyStmt { span: DUMMY_SP })).collect(),
+            shebang: None,
+        })
+    }
+
+    fn check(options: &InstrumentOptions, source: &str) -> Option<String> {
+        exceeded_file_limit(options, &program(0), || Some(source.to_string()))
+    }
+
+    /// 8 行、每行 600 字节
+    fn long_lines() -> String {
+        vec!["x".repeat(600); 8].join("\n")
+    }
+
+    #[test]
+    fn minified_by_average_line_length_by_default() {
+        let reason = check(&InstrumentOptions::default(), &long_lines()).unwrap();
+        assert!(reason.contains("平均每行"), "{reason}");
+    }
+
+    #[test]
+    fn small_single_line_file_is_not_minified() {
+        assert_eq!(check(&InstrumentOptions::default(), &"x".repeat(MINIFIED_MIN_SIZE - 1)), None);
+    }
+
+    #[test]
+    fn short_lines_are_not_minified() {
+        assert_eq!(check(&InstrumentOptions::default(), &vec!["x".repeat(80); 100].join("\n")), None);
+    }
+
+    #[test]
+    fn max_line_length_replaces_average_check() {
+        let options = InstrumentOptions { max_line_length: Some(1000), ..Default::default() };
+        assert_eq!(check(&options, &long_lines()), None);
+    }
+
+    #[test]
+    fn missing_source_skips_source_checks() {
+        assert_eq!(exceeded_file_limit(&InstrumentOptions::default(), &program(3), || None), None);
+    }
+
+    #[test]
+    fn max_source_size_boundary() {
+        let options = InstrumentOptions { max_source_size: Some(10), ..Default::default() };
+        assert_eq!(check(&options, &"a".repeat(10)), None);
+        assert!(check(&options, &"a".repeat(11)).unwrap().contains("maxSourceSize 10"));
+    }
+
+    #[test]
+    fn max_line_length_boundary() {
+        let options = InstrumentOptions { max_line_length: Some(5), ..Default::default() };
+        assert_eq!(check(&options, "12345\n1"), None);
+        let reason = check(&options, "1\n123456").unwrap();
+        assert!(reason.starts_with("第 2 行 6 个字符"), "{reason}");
+    }
+
+    #[test]
+    fn max_line_length_counts_chars_not_bytes() {
+        let options = InstrumentOptions { max_line_length: Some(5), ..Default::default() };
+        assert_eq!(check(&options, "中文字符串"), None);
+    }
+
+    #[test]
+    fn max_line_length_ignores_crlf() {
+        let options = InstrumentOptions { max_line_length: Some(5), ..Default::default() };
+        assert_eq!(check(&options, "12345\r\n12345\r\n"), None);
+        assert!(check(&options, "12345\r\n123456\r\n").unwrap().starts_with("第 2 行 6 个字符"));
+    }
+
+    #[test]
+    fn max_statements_boundary() {
+        let options = InstrumentOptions { max_statements: Some(3), ..Default::default() };
+        assert_eq!(exceeded_file_limit(&options, &program(3), || None), None);
+        assert_eq!(
+            exceeded_file_limit(&options, &program(4), || None).as_deref(),
+            Some("4 条语句，超过 maxStatements 3")
+        );
+    }
+}
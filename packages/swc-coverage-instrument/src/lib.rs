@@ -1,6 +1,15 @@
+mod changed_lines;
+mod ci_env;
+mod file_limits;
+mod options;
 mod source_coverage;
 mod coverage_template;
 mod visitors;
 
-pub use source_coverage::{Range, SourceCoverage};
-pub use visitors::coverage_visitor::create_coverage_instrumentation_visitor;
+pub use changed_lines::{lines_for_file, parse_unified_diff, ChangedLines, LineRange};
+pub use file_limits::exceeded_file_limit;
+pub use options::{CanyonOptions, HitMode, InstrumentMode, InstrumentOptions, UploadOptions};
+pub use source_coverage::{BranchMapping, FunctionMapping, Location, Range, SourceCoverage};
+pub use visitors::coverage_visitor::{
+    create_coverage_instrumentation_visitor, create_coverage_instrumentation_visitor_with_comments,
+};
@@ -0,0 +1,241 @@
+//! 插件配置，对应 .swcrc 中 `plugins: [["swc-plugin-istanbul", { ... }]]` 的第二项
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::changed_lines::ChangedLines;
+
+/// 插桩配置
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct InstrumentOptions {
+    /// Canyon 元数据与其它配置平铺在同一层，和 babel-plugin-canyon 保持一致
+    #[serde(flatten)]
+    pub canyon: CanyonOptions,
+    /// Canyon 字段 -> 环境变量名（一个或多个，取第一个有值的），优先于内置的 CI 映射：
+    /// `{ "sha": "MY_COMMIT", "branch": ["MY_BRANCH", "FALLBACK_BRANCH"] }`
+    #[serde(deserialize_with = "deserialize_env_mapping")]
+    pub env_mapping: IndexMap<String, Vec<String>>,
+    /// 只在这些 swc env（envName，默认取 NODE_ENV）下插桩，为空表示不限制
+    #[serde(alias = "onlyWhenEnv")]
+    pub enabled_envs: Vec<String>,
+    /// 开关环境变量名，如 `CANYON_ENABLE`：值为 true/1 时强制插桩，false/0 时强制跳过，未设置时按 enabledEnvs 判断
+    pub enable_env_var: Option<String>,
+    /// 浏览器端定时把覆盖率 POST 到 dsn，需同时配置 dsn
+    pub upload: Option<UploadOptions>,
+    /// 额外跳过的函数 / 变量名或调用路径（正则），在内置的辅助函数、React Refresh、HMR 规则之外生效：
+    /// `["^__vite_", "^console\\.", "^module\\.exports$"]`
+    pub ignore_patterns: Vec<String>,
+    /// 名字匹配这些正则的函数（含推断出的名字）整个跳过：不登记到 fnMap，函数体内的语句和分支也不插桩
+    pub ignore_function_names: Vec<String>,
+    /// 同 ignoreFunctionNames，只作用于类方法（含 getter / setter，私有方法的名字带 `#`），对应 Istanbul 的 ignoreClassMethods
+    pub ignore_class_methods: Vec<String>,
+    /// 源码超过该字节数的文件不插桩
+    pub max_source_size: Option<usize>,
+    /// 语句数超过该值的文件不插桩
+    pub max_statements: Option<usize>,
+    /// 有任意一行超过该字符数的文件视为压缩代码，不插桩
+    pub max_line_length: Option<usize>,
+    /// 增量插桩：文件 -> 变更行（`[start, end]` 或单个行号），只登记和变更行有交集的语句、函数和分支，
+    /// 不在其中的文件不插桩；为空表示全量插桩：`{ "src/App.tsx": [[10, 24], 31] }`
+    pub changed_lines: ChangedLines,
+    /// unified diff 文件（如 `git diff -U0 origin/main > changes.diff`），解析后并入 changedLines
+    pub diff_file: Option<String>,
+    /// 插桩粒度，默认 full
+    pub instrument_mode: InstrumentMode,
+    /// 计数方式，默认 count
+    pub hit_mode: HitMode,
+}
+
+/// 计数器的写法
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HitMode {
+    /// `cov().s[n]++`，记录执行次数
+    #[default]
+    Count,
+    /// `cov().s[n] = 1`，只记录是否执行过（命中次数最多为 1），热点循环中开销更小
+    Boolean,
+}
+
+/// 插桩粒度：省略的类型在覆盖率对象中为空的 map，报告中对应的覆盖率显示为 100%（0/0）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstrumentMode {
+    /// 语句、函数、分支
+    #[default]
+    Full,
+    /// 语句和函数，不插分支计数器
+    Statements,
+    /// 只插函数计数器，开销最小，适合生产环境冒烟测试
+    Functions,
+}
+
+impl InstrumentMode {
+    pub fn counts_statements(self) -> bool {
+        matches!(self, Self::Full | Self::Statements)
+    }
+
+    pub fn counts_branches(self) -> bool {
+        self == Self::Full
+    }
+}
+
+/// 浏览器上报配置
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UploadOptions {
+    /// 定时上报间隔（毫秒），页面隐藏和卸载时也会上报
+    pub interval: u32,
+}
+
+impl Default for UploadOptions {
+    fn default() -> Self {
+        Self { interval: 15000 }
+    }
+}
+
+impl InstrumentOptions {
+    /// 当前构建是否需要插桩
+    pub fn is_enabled(&self, env_name: Option<&str>, lookup: impl Fn(&str) -> Option<String>) -> bool {
+        if let Some(value) = self.enable_env_var.as_deref().and_then(&lookup) {
+            match value.trim().to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" | "on" => return true,
+                "false" | "0" | "no" | "off" => return false,
+                _ => {}
+            }
+        }
+        self.enabled_envs.is_empty()
+            || env_name.is_some_and(|env| self.enabled_envs.iter().any(|e| e == env))
+    }
+}
+
+/// Canyon 收集端需要的元数据，写入每个文件的覆盖率对象，字段名与 babel-plugin-canyon 一致
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CanyonOptions {
+    #[serde(rename = "projectID", deserialize_with = "deserialize_string_like")]
+    pub project_id: Option<String>,
+    #[serde(deserialize_with = "deserialize_string_like")]
+    pub sha: Option<String>,
+    #[serde(deserialize_with = "deserialize_string_like")]
+    pub branch: Option<String>,
+    #[serde(deserialize_with = "deserialize_string_like")]
+    pub reporter: Option<String>,
+    #[serde(deserialize_with = "deserialize_string_like")]
+    pub dsn: Option<String>,
+    #[serde(rename = "instrumentCwd", deserialize_with = "deserialize_string_like")]
+    pub instrument_cwd: Option<String>,
+    #[serde(rename = "buildProvider", deserialize_with = "deserialize_string_like")]
+    pub build_provider: Option<String>,
+    #[serde(rename = "buildID", deserialize_with = "deserialize_string_like")]
+    pub build_id: Option<String>,
+    #[serde(rename = "compareTarget", deserialize_with = "deserialize_string_like")]
+    pub compare_target: Option<String>,
+}
+
+impl CanyonOptions {
+    /// 按 babel-plugin-canyon 的字段顺序列出已设置的字段
+    pub fn fields(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("projectID", &self.project_id),
+            ("sha", &self.sha),
+            ("branch", &self.branch),
+            ("reporter", &self.reporter),
+            ("dsn", &self.dsn),
+            ("instrumentCwd", &self.instrument_cwd),
+            ("buildProvider", &self.build_provider),
+            ("buildID", &self.build_id),
+            ("compareTarget", &self.compare_target),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.as_deref().map(|v| (key, v)))
+        .collect()
+    }
+}
+
+/// projectID、buildID 等常被写成数字，统一转成字符串
+fn deserialize_string_like<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(match Option::<serde_json::Value>::deserialize(deserializer)? {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(s)) => Some(s),
+        Some(other) => Some(other.to_string()),
+    })
+}
+
+fn deserialize_env_mapping<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<IndexMap<String, Vec<String>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let raw = IndexMap::<String, OneOrMany>::deserialize(deserializer)?;
+    Ok(raw
+        .into_iter()
+        .map(|(key, names)| match names {
+            OneOrMany::One(name) => (key, vec![name]),
+            OneOrMany::Many(names) => (key, names),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(enabled_envs: &[&str], enable_env_var: Option<&str>) -> InstrumentOptions {
+        InstrumentOptions {
+            enabled_envs: enabled_envs.iter().map(|e| e.to_string()).collect(),
+            enable_env_var: enable_env_var.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn lookup(value: Option<&'static str>) -> impl Fn(&str) -> Option<String> {
+        move |name| (name == "CANYON_ENABLE").then_some(value).flatten().map(str::to_string)
+    }
+
+    #[test]
+    fn enabled_everywhere_without_envs() {
+        let options = options(&[], None);
+        assert!(options.is_enabled(None, lookup(None)));
+        assert!(options.is_enabled(Some("production"), lookup(None)));
+    }
+
+    #[test]
+    fn only_enabled_in_listed_envs() {
+        let options = options(&["test", "e2e"], None);
+        assert!(options.is_enabled(Some("test"), lookup(None)));
+        assert!(options.is_enabled(Some("e2e"), lookup(None)));
+        assert!(!options.is_enabled(Some("production"), lookup(None)));
+        assert!(!options.is_enabled(None, lookup(None)));
+    }
+
+    #[test]
+    fn enable_env_var_overrides_envs() {
+        let options = options(&["test"], Some("CANYON_ENABLE"));
+        assert!(options.is_enabled(Some("production"), lookup(Some("true"))));
+        assert!(options.is_enabled(None, lookup(Some(" ON "))));
+        assert!(!options.is_enabled(Some("test"), lookup(Some("0"))));
+        assert!(!options.is_enabled(Some("test"), lookup(Some("false"))));
+    }
+
+    #[test]
+    fn unknown_or_missing_enable_value_falls_back_to_envs() {
+        let options = options(&["test"], Some("CANYON_ENABLE"));
+        assert!(options.is_enabled(Some("test"), lookup(Some("maybe"))));
+        assert!(!options.is_enabled(Some("production"), lookup(Some("maybe"))));
+        assert!(!options.is_enabled(Some("production"), lookup(None)));
+    }
+
+    #[test]
+    fn only_when_env_alias() {
+        let options: InstrumentOptions = serde_json::from_str(r#"{ "onlyWhenEnv": ["test"] }"#).unwrap();
+        assert_eq!(options.enabled_envs, vec!["test"]);
+        assert!(!options.is_enabled(Some("development"), lookup(None)));
+    }
+}
@@ -1,19 +1,37 @@
 //! Istanbul 格式的 coverage 数据结构，学 old 的 source_coverage
 
 use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// 位置 { line, column }
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Location {
     pub line: u32,
+    /// source map 重映射后的结束列可能是 null（Infinity），按行尾处理
+    #[serde(serialize_with = "serialize_column", deserialize_with = "deserialize_column")]
     pub column: u32,
 }
 
+fn serialize_column<S: Serializer>(column: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+    if *column == u32::MAX {
+        serializer.serialize_none()
+    } else {
+        serializer.serialize_u32(*column)
+    }
+}
+
+fn deserialize_column<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    Ok(Option::<u32>::deserialize(deserializer)?.unwrap_or(u32::MAX))
+}
+
 /// 范围 { start, end }
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Range {
     pub start: Location,
     pub end: Location,
+    /// istanbul ignore 等标记为跳过的项，汇总时计入 skipped
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip: bool,
 }
 
 impl Range {
@@ -27,15 +45,39 @@ impl Range {
                 line: end_line,
                 column: end_column,
             },
+            skip: false,
         }
     }
 }
 
-/// 收集 statement 的 coverage 数据，学 old 的 SourceCoverage
+/// fnMap 中的一项 { name, decl, loc, line }
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FunctionMapping {
+    pub name: String,
+    pub decl: Range,
+    pub loc: Range,
+    pub line: u32,
+}
+
+/// branchMap 中的一项 { loc, type, locations, line }
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BranchMapping {
+    pub loc: Range,
+    #[serde(rename = "type")]
+    pub branch_type: String,
+    pub locations: Vec<Range>,
+    pub line: u32,
+}
+
+/// 收集 statement / function / branch 的 coverage 数据，学 old 的 SourceCoverage
 #[derive(Clone, Debug, Default)]
 pub struct SourceCoverage {
     pub statement_map: IndexMap<u32, Range>,
     pub s: IndexMap<u32, u32>,
+    pub fn_map: IndexMap<u32, FunctionMapping>,
+    pub f: IndexMap<u32, u32>,
+    pub branch_map: IndexMap<u32, BranchMapping>,
+    pub b: IndexMap<u32, Vec<u32>>,
     next_id: u32,
 }
 
@@ -52,4 +94,40 @@ impl SourceCoverage {
         self.next_id += 1;
         id
     }
+
+    /// 添加 function，返回 id；没有名字时按 Istanbul 的习惯命名为 (anonymous_N)
+    pub fn new_function(&mut self, name: Option<&str>, decl: &Range, loc: &Range) -> u32 {
+        let id = self.fn_map.len() as u32;
+        let name = match name {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => format!("(anonymous_{id})"),
+        };
+        self.fn_map.insert(
+            id,
+            FunctionMapping {
+                name,
+                decl: *decl,
+                loc: *loc,
+                line: loc.start.line,
+            },
+        );
+        self.f.insert(id, 0);
+        id
+    }
+
+    /// 添加 branch 及其各条分支路径，返回 id
+    pub fn new_branch(&mut self, branch_type: &str, loc: &Range, locations: &[Range]) -> u32 {
+        let id = self.branch_map.len() as u32;
+        self.branch_map.insert(
+            id,
+            BranchMapping {
+                loc: *loc,
+                branch_type: branch_type.to_string(),
+                locations: locations.to_vec(),
+                line: loc.start.line,
+            },
+        );
+        self.b.insert(id, vec![0; locations.len()]);
+        id
+    }
 }
@@ -1,8 +1,9 @@
 use std::cell::RefCell;
 use std::hash::{Hash, Hasher};
 
+use regex::Regex;
 use swc_core::{
-    common::{Span, Spanned, util::take::Take, DUMMY_SP},
+    common::{comments::Comments, util::take::Take, BytePos, Span, Spanned, DUMMY_SP},
     ecma::{
         ast::*,
         utils::IsDirective,
@@ -11,9 +12,13 @@ use swc_core::{
 };
 use serde_json;
 
+use crate::changed_lines::{lines_for_file, LineRange};
+use crate::options::InstrumentOptions;
 use crate::source_coverage::{Range, SourceCoverage};
 use crate::coverage_template;
 
+use super::skip_list::{compile_patterns, FileMarkers, SkipList};
+
 /// 覆盖率插桩 Visitor
 pub struct CoverageVisitor {
     filename: String,
@@ -21,19 +26,45 @@ pub struct CoverageVisitor {
     cov_fn_ident: Ident,
     get_range: Box<dyn Fn(&Span) -> Range + Send + Sync>,
     ast_json: RefCell<Option<String>>,
+    /// 从上下文推断出的匿名函数名和名字的位置，由紧接着访问的函数表达式 / 箭头函数取走
+    fn_name_hint: Option<(String, Option<Span>)>,
+    /// 其它转换生成的代码，整条语句不插桩
+    skip_list: SkipList,
+    /// 配置的 ignoreFunctionNames / ignoreClassMethods
+    ignore_function_names: Vec<Regex>,
+    ignore_class_methods: Vec<Regex>,
+    /// 用于识别 `@generated` 等文件头注释，没有注释信息时不检查
+    comments: Option<Box<dyn Comments>>,
+    /// 增量插桩时本文件的变更行，None 表示全量插桩
+    changed_lines: Option<Vec<LineRange>>,
+    options: InstrumentOptions,
 }
 
 impl CoverageVisitor {
-    pub fn new(filename: String, get_range: Box<dyn Fn(&Span) -> Range + Send + Sync>) -> Self {
+    pub fn new(
+        filename: String,
+        options: InstrumentOptions,
+        get_range: Box<dyn Fn(&Span) -> Range + Send + Sync>,
+        comments: Option<Box<dyn Comments>>,
+    ) -> Self {
         let mut hasher = std::collections::hash_map::DefaultHasher::new();
         filename.hash(&mut hasher);
         let cov_fn_name = format!("cov_{}", hasher.finish());
+        let changed_lines = (!options.changed_lines.is_empty())
+            .then(|| lines_for_file(&options.changed_lines, &filename).unwrap_or_default().to_vec());
         Self {
             cov_fn_ident: Ident::new(cov_fn_name.into(), DUMMY_SP, Default::default()),
             filename,
             cov: RefCell::new(SourceCoverage::new()),
             get_range,
             ast_json: RefCell::new(None),
+            fn_name_hint: None,
+            skip_list: SkipList::new(&options.ignore_patterns),
+            ignore_function_names: compile_patterns("ignoreFunctionNames", &options.ignore_function_names),
+            ignore_class_methods: compile_patterns("ignoreClassMethods", &options.ignore_class_methods),
+            comments,
+            changed_lines,
+            options,
         }
     }
 
@@ -41,6 +72,239 @@ impl CoverageVisitor {
         (self.get_range)(span)
     }
 
+    /// 增量插桩时范围是否和变更行有交集，全量插桩时总是 true
+    fn is_changed(&self, range: &Range) -> bool {
+        self.changed_lines.as_ref().is_none_or(|lines| {
+            lines
+                .iter()
+                .any(|line| line.overlaps(range.start.line, range.end.line))
+        })
+    }
+
+    /// 创建语句计数器 cov_xxx().s[id]++，生成的语句使用 DUMMY_SP，不会被再次插桩；
+    /// 不在变更行内的语句和 instrumentMode 为 functions 时不登记
+    fn mark_prepend_stmt_counter(&self, span: &Span) -> Option<Stmt> {
+        let range = self.get_range(span);
+        if !self.options.instrument_mode.counts_statements() || !self.is_changed(&range) {
+            return None;
+        }
+        let id = self.cov.borrow_mut().new_statement(&range);
+        println!("    -> 注入 statement counter s[{id}], range: {range:?}");
+        Some(Stmt::Expr(ExprStmt {
+            span: DUMMY_SP,
+            expr: Box::new(coverage_template::create_increase_counter_expr(id, &self.cov_fn_ident, self.options.hit_mode)),
+        }))
+    }
+
+    /// 创建语句计数表达式 cov_xxx().s[id]++，用于只能放表达式的位置（变量初始值、类属性值）
+    fn mark_expr_counter(&self, span: &Span) -> Option<Expr> {
+        let range = self.get_range(span);
+        if !self.options.instrument_mode.counts_statements() || !self.is_changed(&range) {
+            return None;
+        }
+        let id = self.cov.borrow_mut().new_statement(&range);
+        println!("    -> 注入 statement counter s[{id}] (表达式), range: {range:?}");
+        Some(coverage_template::create_increase_counter_expr(id, &self.cov_fn_ident, self.options.hit_mode))
+    }
+
+    /// 登记分支及其各条路径，返回 branch id；loc 为插桩生成的节点、不在变更行内或 instrumentMode 不含分支时不登记
+    fn new_branch(&self, branch_type: &str, loc: &Span, paths: &[Span]) -> Option<u32> {
+        if loc.is_dummy() || !self.options.instrument_mode.counts_branches() {
+            return None;
+        }
+        let loc = self.get_range(loc);
+        if !self.is_changed(&loc) {
+            return None;
+        }
+        let locations: Vec<_> = paths.iter().map(|path| self.get_range(path)).collect();
+        let id = self.cov.borrow_mut().new_branch(branch_type, &loc, &locations);
+        println!("    -> 注入 branch b[{id}] ({branch_type}, {} 条路径), loc: {loc:?}", paths.len());
+        Some(id)
+    }
+
+    fn branch_counter(&self, id: u32, index: u32) -> Expr {
+        coverage_template::create_increase_branch_counter_expr(id, index, &self.cov_fn_ident, self.options.hit_mode)
+    }
+
+    fn branch_counter_stmt(&self, id: u32, index: u32) -> Stmt {
+        Stmt::Expr(ExprStmt {
+            span: DUMMY_SP,
+            expr: Box::new(self.branch_counter(id, index)),
+        })
+    }
+
+    /// 逻辑表达式的叶子按从左到右的顺序各加一个分支计数器，叶子内部照常遍历
+    fn instrument_logical_leaves(&mut self, expr: &mut Expr, branch: u32, index: &mut u32) {
+        match expr {
+            Expr::Bin(bin) if is_logical_op(bin.op) => {
+                self.instrument_logical_leaves(&mut bin.left, branch, index);
+                self.instrument_logical_leaves(&mut bin.right, branch, index);
+            }
+            Expr::Paren(paren) if is_logical_expr(&paren.expr) => {
+                self.instrument_logical_leaves(&mut paren.expr, branch, index);
+            }
+            _ => {
+                expr.visit_mut_with(self);
+                prepend_expr_counter(self.branch_counter(branch, *index), expr);
+                *index += 1;
+            }
+        }
+    }
+
+    /// 登记函数并创建函数计数器 cov_xxx().f[id]++
+    /// decl: 函数名的位置，匿名函数取函数起始的一个字符；loc: 函数体的位置
+    fn mark_function_counter(&self, name: Option<&str>, decl: &Span, loc: &Span) -> Option<Stmt> {
+        let decl = self.get_range(decl);
+        let loc = self.get_range(loc);
+        if !self.is_changed(&decl) && !self.is_changed(&loc) {
+            return None;
+        }
+        let id = self.cov.borrow_mut().new_function(name, &decl, &loc);
+        println!("    -> 注入 function counter f[{id}] ({name:?}), loc: {loc:?}");
+        Some(Stmt::Expr(ExprStmt {
+            span: DUMMY_SP,
+            expr: Box::new(coverage_template::create_increase_fn_counter_expr(id, &self.cov_fn_ident, self.options.hit_mode)),
+        }))
+    }
+
+    /// 函数类节点统一处理：先登记函数（id 按进入顺序分配），再遍历子节点，最后把计数器插到函数体开头；
+    /// 名字匹配 ignoreFunctionNames 的函数整个跳过
+    fn instrument_function<N: VisitMutWith<Self>>(
+        &mut self,
+        node: &mut N,
+        name: Option<&str>,
+        decl: Span,
+        body: impl Fn(&mut N) -> Option<&mut BlockStmt>,
+    ) {
+        if is_ignored_name(&self.ignore_function_names, name) {
+            println!("    -> 跳过函数 {name:?}（ignoreFunctionNames）");
+            return;
+        }
+        let counter = match body(node) {
+            Some(block) if !block.span.is_dummy() && !decl.is_dummy() => {
+                self.mark_function_counter(name, &decl, &block.span)
+            }
+            _ => None,
+        };
+        node.visit_mut_children_with(self);
+        if let (Some(counter), Some(block)) = (counter, body(node)) {
+            insert_after_directives(&mut block.stmts, counter);
+        }
+    }
+
+    /// expr 是匿名函数时记下推断的名字，类似 Babel 的函数名推断：
+    /// `const handler = () => {}`、`{ key: function () {} }`、`export default () => {}` 等
+    fn hint_fn_name(&mut self, expr: &Expr, name: Option<String>, span: Option<Span>) {
+        let is_anonymous_fn = matches!(
+            unwrap_expr(expr),
+            Expr::Fn(FnExpr { ident: None, .. }) | Expr::Arrow(_)
+        );
+        self.fn_name_hint = match name {
+            Some(name) if is_anonymous_fn => Some((name, span)),
+            _ => None,
+        };
+    }
+
+    /// 需要计为语句的模块声明，返回计数范围：
+    /// - `export default expr` / `export = expr`：计数器放在 export 前面，
+    ///   不包进逗号表达式，避免 `export default () => {}` 丢掉 default 函数名
+    /// - export 的函数 / 类 / 变量：和普通声明一样在子节点中插桩
+    /// - import、re-export 等没有可执行代码，不计数
+    fn module_decl_stmt_span(decl: &ModuleDecl) -> Option<Span> {
+        match decl {
+            ModuleDecl::ExportDefaultExpr(export) if !export.span.is_dummy() => Some(export.span),
+            ModuleDecl::TsExportAssignment(export) if !export.span.is_dummy() => Some(export.span),
+            ModuleDecl::ExportDecl(export) if !export.span.is_dummy() && is_counted_decl(&export.decl) => {
+                Some(export.span)
+            }
+            ModuleDecl::ExportDefaultDecl(ExportDefaultDecl {
+                span,
+                decl: DefaultDecl::Class(_),
+            }) if !span.is_dummy() => Some(*span),
+            ModuleDecl::ExportDefaultExpr(_)
+            | ModuleDecl::TsExportAssignment(_)
+            | ModuleDecl::Import(_)
+            | ModuleDecl::ExportDecl(_)
+            | ModuleDecl::ExportNamed(_)
+            | ModuleDecl::ExportDefaultDecl(_)
+            | ModuleDecl::ExportAll(_)
+            | ModuleDecl::TsImportEquals(_)
+            | ModuleDecl::TsNamespaceExport(_) => None,
+            #[cfg(swc_ast_unknown)]
+            _ => None,
+        }
+    }
+
+    /// 生成的辅助代码不计数也不进入子节点
+    fn is_skipped_item(&self, item: &ModuleItem) -> bool {
+        let skipped = match item {
+            ModuleItem::Stmt(stmt) => self.skip_list.is_skipped_stmt(stmt),
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(export)) => self.skip_list.is_skipped_decl(&export.decl),
+            ModuleItem::ModuleDecl(_) => false,
+            #[cfg(swc_ast_unknown)]
+            _ => false,
+        };
+        if skipped {
+            println!("  -> 跳过生成的代码，位置: {:?}", item.span());
+        }
+        skipped
+    }
+
+    /// 不需要插桩的文件，原样返回：
+    /// - 已经插桩过：顶层有 `function cov_xxx() { ... var gcv = "__coverage__"; ... }`（本插件或 babel-plugin-istanbul 生成），
+    ///   或顶层函数中的覆盖率数据带 `_coverageSchema` 标记（函数名被压缩改掉时），再插桩会重复计数
+    /// - 文件头注释带 `@generated`
+    /// - 压缩文件：`*.min.js` 等，按行长判断的部分见 file_limits
+    /// - 增量插桩时没有变更行
+    fn skip_file_reason(&self, program: &Program) -> Option<&'static str> {
+        if self.changed_lines.as_ref().is_some_and(Vec::is_empty) {
+            return Some("不在变更范围内");
+        }
+        let body: Vec<&Stmt> = match program {
+            Program::Module(m) => m.body.iter().filter_map(ModuleItem::as_stmt).collect(),
+            Program::Script(s) => s.body.iter().collect(),
+            #[cfg(swc_ast_unknown)]
+            _ => vec![],
+        };
+        if body.iter().any(|stmt| is_coverage_preamble(stmt)) {
+            return Some("已插桩");
+        }
+
+        let first_pos = match program {
+            Program::Module(m) => m.body.first().map(|item| item.span_lo()),
+            Program::Script(s) => s.body.first().map(|stmt| stmt.span_lo()),
+            #[cfg(swc_ast_unknown)]
+            _ => None,
+        };
+        let is_generated = self.comments.as_ref().is_some_and(|comments| {
+            [Some(program.span_lo()), first_pos]
+                .into_iter()
+                .flatten()
+                .filter_map(|pos| comments.get_leading(pos))
+                .flatten()
+                .any(|comment| comment.text.contains("@generated"))
+        });
+        if is_generated {
+            return Some("@generated");
+        }
+
+        let is_minified = [".min.js", ".min.mjs", ".min.cjs"]
+            .iter()
+            .any(|ext| self.filename.ends_with(ext));
+        is_minified.then_some("压缩文件")
+    }
+
+    /// 语句是否需要计数：跳过插桩生成的语句、空语句和块语句；
+    /// 声明中和 istanbul 一样只计类声明，另外计会生成运行时代码的 enum，见 is_counted_decl
+    fn should_count_stmt(stmt: &Stmt) -> bool {
+        match stmt {
+            _ if stmt.span().is_dummy() => false,
+            Stmt::Decl(decl) => is_counted_decl(decl),
+            Stmt::Empty(_) | Stmt::Block(_) => false,
+            _ => true,
+        }
+    }
+
     /// 创建覆盖率初始化语句
     fn create_coverage_init_stmts(&self) -> Vec<Stmt> {
         let cov = self.cov.borrow();
@@ -49,7 +313,8 @@ impl CoverageVisitor {
             &self.filename, 
             &self.cov_fn_ident, 
             &cov,
-            ast_json.as_deref()
+            ast_json.as_deref(),
+            &self.options,
         )
     }
 }
@@ -57,6 +322,13 @@ impl CoverageVisitor {
 impl VisitMut for CoverageVisitor {
     fn visit_mut_program(&mut self, program: &mut Program) {
         println!("=== visit_mut_program: 开始处理程序 ===");
+        if let Some(reason) = self.skip_file_reason(program) {
+            println!("=== 跳过文件 {}：{reason} ===", self.filename);
+            return;
+        }
+        let markers = FileMarkers::scan(program);
+        println!("  -> 文件标记: {markers:?}");
+        self.skip_list.set_markers(markers);
         
         // 序列化 AST 为 JSON
         match serde_json::to_string(program) {
@@ -65,7 +337,7 @@ impl VisitMut for CoverageVisitor {
                 *self.ast_json.borrow_mut() = Some(json);
             }
             Err(e) => {
-                println!("  -> AST 序列化失败: {}", e);
+                println!("  -> AST 序列化失败: {e}");
             }
         }
         
@@ -74,30 +346,922 @@ impl VisitMut for CoverageVisitor {
         let stmts = self.create_coverage_init_stmts();
         println!("=== 创建了 {} 个初始化语句 ===", stmts.len());
 
+        // 插入到指令序言（"use strict"、"use client" 等）之后，否则指令会失效；
+        // shebang 存在 Module/Script 的 shebang 字段中，不受影响
         match program {
             Program::Module(m) => {
-                println!("  -> 插入到 Module 顶部");
-                for stmt in stmts.into_iter().rev() {
-                    m.body.insert(0, ModuleItem::Stmt(stmt));
-                }
+                let index = m.body.iter().take_while(|item| item.directive_continue()).count();
+                println!("  -> 插入到 Module 第 {index} 项");
+                m.body.splice(index..index, stmts.into_iter().map(ModuleItem::Stmt));
             }
             Program::Script(s) => {
-                println!("  -> 插入到 Script 顶部");
-                for stmt in stmts.into_iter().rev() {
-                    s.body.insert(0, stmt);
-                }
+                let index = s.body.iter().take_while(|stmt| stmt.directive_continue()).count();
+                println!("  -> 插入到 Script 第 {index} 项");
+                s.body.splice(index..index, stmts);
             }
+            #[cfg(swc_ast_unknown)]
             _ => {}
         }
         println!("=== visit_mut_program: 处理完成 ===");
     }
+
+    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+        println!("=== visit_mut_module_items: 处理 {} 个模块语句 ===", items.len());
+        let mut new_items = Vec::with_capacity(items.len() * 2);
+        let mut in_prologue = true;
+        for mut item in items.drain(..) {
+            // 指令序言中的 "use strict" 等不计数
+            in_prologue = in_prologue && item.directive_continue();
+            if self.is_skipped_item(&item) {
+                new_items.push(item);
+                continue;
+            }
+            let counted_span = match &item {
+                ModuleItem::Stmt(stmt) => (!in_prologue && Self::should_count_stmt(stmt)).then(|| stmt.span()),
+                ModuleItem::ModuleDecl(decl) => Self::module_decl_stmt_span(decl),
+                #[cfg(swc_ast_unknown)]
+                _ => None,
+            };
+            // 计数器只放在被计数的项前面，import 之间不会插入其它语句，求值顺序不变
+            if let Some(counter) = counted_span.and_then(|span| self.mark_prepend_stmt_counter(&span)) {
+                new_items.push(ModuleItem::Stmt(counter));
+            }
+            item.visit_mut_with(self);
+            new_items.push(item);
+        }
+        *items = new_items;
+    }
+
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        println!("=== visit_mut_stmts: 处理 {} 个块语句 ===", stmts.len());
+        let mut new_stmts = Vec::with_capacity(stmts.len() * 2);
+        let mut in_prologue = true;
+        for mut stmt in stmts.drain(..) {
+            in_prologue = in_prologue && stmt.directive_continue();
+            if self.skip_list.is_skipped_stmt(&stmt) {
+                println!("  -> 跳过生成的代码，位置: {:?}", stmt.span());
+                new_stmts.push(stmt);
+                continue;
+            }
+            if !in_prologue && Self::should_count_stmt(&stmt) {
+                new_stmts.extend(self.mark_prepend_stmt_counter(&stmt.span()));
+            }
+            stmt.visit_mut_with(self);
+            new_stmts.push(stmt);
+        }
+        *stmts = new_stmts;
+    }
+
+    /// TS 类型声明和 declare 声明不会执行，不插桩
+    fn visit_mut_decl(&mut self, decl: &mut Decl) {
+        if is_type_only_decl(decl) {
+            return;
+        }
+        decl.visit_mut_children_with(self);
+    }
+
+    /// enum 成员初始值不插桩：插入计数器会把常量成员变成计算成员，
+    /// 后面没写初始值的成员会因此编译失败
+    fn visit_mut_ts_enum_decl(&mut self, _: &mut TsEnumDecl) {}
+
+    /// 抽象成员、declare 属性、重载签名和索引签名没有运行时代码
+    fn visit_mut_class_member(&mut self, member: &mut ClassMember) {
+        let type_only = match member {
+            ClassMember::Method(method) => method.is_abstract || method.function.body.is_none(),
+            ClassMember::PrivateMethod(method) => method.is_abstract || method.function.body.is_none(),
+            ClassMember::Constructor(constructor) => constructor.body.is_none(),
+            ClassMember::ClassProp(prop) => prop.declare || prop.is_abstract,
+            ClassMember::AutoAccessor(accessor) => accessor.is_abstract,
+            ClassMember::TsIndexSignature(_) => true,
+            _ => false,
+        };
+        if !type_only {
+            member.visit_mut_children_with(self);
+        }
+    }
+
+    fn visit_mut_fn_decl(&mut self, decl: &mut FnDecl) {
+        let name = decl.ident.sym.to_string();
+        let decl_span = decl.ident.span;
+        self.instrument_function(&mut *decl.function, Some(&name), decl_span, |f| f.body.as_mut());
+    }
+
+    fn visit_mut_fn_expr(&mut self, expr: &mut FnExpr) {
+        let hint = self.fn_name_hint.take();
+        let (name, decl_span) = match (&expr.ident, hint) {
+            (Some(ident), _) => (Some(ident.sym.to_string()), ident.span),
+            (None, Some((name, span))) => (Some(name), span.unwrap_or_else(|| anonymous_decl_span(expr.function.span))),
+            (None, None) => (None, anonymous_decl_span(expr.function.span)),
+        };
+        self.instrument_function(&mut *expr.function, name.as_deref(), decl_span, |f| f.body.as_mut());
+    }
+
+    /// 箭头函数：`x => x * 2` 改写为 `x => { cov().f[n]++; cov().s[m]++; return x * 2; }`，
+    /// 仍是箭头函数，this / arguments 语义不变；返回对象字面量时 codegen 会保留括号
+    fn visit_mut_arrow_expr(&mut self, arrow: &mut ArrowExpr) {
+        let (name, decl_span) = match self.fn_name_hint.take() {
+            Some((name, span)) => (Some(name), span.unwrap_or_else(|| anonymous_decl_span(arrow.span))),
+            None => (None, anonymous_decl_span(arrow.span)),
+        };
+        // 被 ignoreFunctionNames 跳过的箭头函数保持原样
+        let ignored = is_ignored_name(&self.ignore_function_names, name.as_deref());
+        if let (BlockStmtOrExpr::Expr(expr), false) = (&mut *arrow.body, ignored) {
+            let span = expr.span();
+            let expr = expr.take();
+            *arrow.body = BlockStmtOrExpr::BlockStmt(BlockStmt {
+                span,
+                stmts: vec![Stmt::Return(ReturnStmt { span, arg: Some(expr) })],
+                ..BlockStmt::dummy()
+            });
+        }
+        self.instrument_function(arrow, name.as_deref(), decl_span, |arrow| match &mut *arrow.body {
+            BlockStmtOrExpr::BlockStmt(block) => Some(block),
+            _ => None,
+        });
+    }
+
+    fn visit_mut_class_method(&mut self, method: &mut ClassMethod) {
+        let name = prop_name(&method.key);
+        if is_ignored_name(&self.ignore_class_methods, name.as_deref()) {
+            println!("    -> 跳过类方法 {name:?}（ignoreClassMethods）");
+            return;
+        }
+        let decl_span = method.key.span();
+        method.key.visit_mut_with(self);
+        self.instrument_function(&mut *method.function, name.as_deref(), decl_span, |f| f.body.as_mut());
+    }
+
+    fn visit_mut_private_method(&mut self, method: &mut PrivateMethod) {
+        let name = format!("#{}", method.key.name);
+        if is_ignored_name(&self.ignore_class_methods, Some(&name)) {
+            println!("    -> 跳过类方法 {name:?}（ignoreClassMethods）");
+            return;
+        }
+        let decl_span = method.key.span;
+        self.instrument_function(&mut *method.function, Some(&name), decl_span, |f| f.body.as_mut());
+    }
+
+    /// `static { ... }` 块在类定义时执行一次，和函数一样登记到 fnMap（名字为 static，decl 为 static 关键字），
+    /// 块内语句照常计数
+    fn visit_mut_static_block(&mut self, block: &mut StaticBlock) {
+        let decl_span = if block.span.is_dummy() {
+            block.span
+        } else {
+            Span::new(block.span.lo, block.span.lo + BytePos(6))
+        };
+        self.instrument_function(block, Some("static"), decl_span, |b| Some(&mut b.body));
+    }
+
+    fn visit_mut_constructor(&mut self, constructor: &mut Constructor) {
+        let decl_span = constructor.key.span();
+        self.instrument_function(constructor, Some("constructor"), decl_span, |c| c.body.as_mut());
+    }
+
+    fn visit_mut_method_prop(&mut self, method: &mut MethodProp) {
+        let name = prop_name(&method.key);
+        let decl_span = method.key.span();
+        method.key.visit_mut_with(self);
+        self.instrument_function(&mut *method.function, name.as_deref(), decl_span, |f| f.body.as_mut());
+    }
+
+    fn visit_mut_getter_prop(&mut self, getter: &mut GetterProp) {
+        let name = prop_name(&getter.key);
+        let decl_span = getter.key.span();
+        self.instrument_function(getter, name.as_deref(), decl_span, |g| g.body.as_mut());
+    }
+
+    fn visit_mut_setter_prop(&mut self, setter: &mut SetterProp) {
+        let name = prop_name(&setter.key);
+        let decl_span = setter.key.span();
+        self.instrument_function(setter, name.as_deref(), decl_span, |s| s.body.as_mut());
+    }
+
+    /// 带初始值的变量声明计为一条语句，范围是整个 declarator: `var a = (cov_xxx().s[0]++, 1)`
+    fn visit_mut_var_declarator(&mut self, declarator: &mut VarDeclarator) {
+        let counter = match &declarator.init {
+            Some(_) if !declarator.span.is_dummy() => self.mark_expr_counter(&declarator.span),
+            _ => None,
+        };
+        declarator.name.visit_mut_with(self);
+        if let (Pat::Ident(binding), Some(init)) = (&declarator.name, &declarator.init) {
+            self.hint_fn_name(init, Some(binding.id.sym.to_string()), Some(binding.id.span));
+        }
+        declarator.init.visit_mut_with(self);
+        self.fn_name_hint = None;
+        if let (Some(counter), Some(init)) = (counter, &mut declarator.init) {
+            prepend_expr_counter(counter, init);
+        }
+    }
+
+    /// 类属性的初始值计为一条语句，范围是初始值表达式
+    fn visit_mut_class_prop(&mut self, prop: &mut ClassProp) {
+        let counter = prop
+            .value
+            .as_ref()
+            .filter(|value| !value.span().is_dummy())
+            .and_then(|value| self.mark_expr_counter(&value.span()));
+        prop.decorators.visit_mut_with(self);
+        prop.key.visit_mut_with(self);
+        if let Some(value) = &prop.value {
+            self.hint_fn_name(value, prop_name(&prop.key), Some(prop.key.span()));
+        }
+        prop.value.visit_mut_with(self);
+        self.fn_name_hint = None;
+        if let (Some(counter), Some(value)) = (counter, &mut prop.value) {
+            prepend_expr_counter(counter, value);
+        }
+    }
+
+    fn visit_mut_private_prop(&mut self, prop: &mut PrivateProp) {
+        let counter = prop
+            .value
+            .as_ref()
+            .filter(|value| !value.span().is_dummy())
+            .and_then(|value| self.mark_expr_counter(&value.span()));
+        prop.decorators.visit_mut_with(self);
+        prop.key.visit_mut_with(self);
+        if let Some(value) = &prop.value {
+            self.hint_fn_name(value, Some(format!("#{}", prop.key.name)), Some(prop.key.span));
+        }
+        prop.value.visit_mut_with(self);
+        self.fn_name_hint = None;
+        if let (Some(counter), Some(value)) = (counter, &mut prop.value) {
+            prepend_expr_counter(counter, value);
+        }
+    }
+
+    /// `accessor count = 0` 的初始值和类属性一样计为一条语句
+    fn visit_mut_auto_accessor(&mut self, accessor: &mut AutoAccessor) {
+        let counter = accessor
+            .value
+            .as_ref()
+            .filter(|value| !value.span().is_dummy())
+            .and_then(|value| self.mark_expr_counter(&value.span()));
+        accessor.decorators.visit_mut_with(self);
+        accessor.key.visit_mut_with(self);
+        if let Some(value) = &accessor.value {
+            let (name, span) = match &accessor.key {
+                Key::Private(key) => (Some(format!("#{}", key.name)), key.span),
+                Key::Public(key) => (prop_name(key), key.span()),
+                #[cfg(swc_ast_unknown)]
+                _ => (None, accessor.span),
+            };
+            self.hint_fn_name(value, name, Some(span));
+        }
+        accessor.value.visit_mut_with(self);
+        self.fn_name_hint = None;
+        if let (Some(counter), Some(value)) = (counter, &mut accessor.value) {
+            prepend_expr_counter(counter, value);
+        }
+    }
+
+    /// `{ key: () => {} }` 以属性名命名
+    fn visit_mut_key_value_prop(&mut self, prop: &mut KeyValueProp) {
+        prop.key.visit_mut_with(self);
+        self.hint_fn_name(&prop.value, prop_name(&prop.key), Some(prop.key.span()));
+        prop.value.visit_mut_with(self);
+        self.fn_name_hint = None;
+    }
+
+    /// `handler = () => {}` 以被赋值的变量命名，成员赋值不推断
+    fn visit_mut_assign_expr(&mut self, expr: &mut AssignExpr) {
+        expr.left.visit_mut_with(self);
+        if let (AssignOp::Assign, AssignTarget::Simple(SimpleAssignTarget::Ident(binding))) = (expr.op, &expr.left) {
+            self.hint_fn_name(&expr.right, Some(binding.id.sym.to_string()), Some(binding.id.span));
+        }
+        expr.right.visit_mut_with(self);
+        self.fn_name_hint = None;
+    }
+
+    /// 默认值 `function f(cb = () => {})`，计为 default-arg 分支：`cb = (cov().b[n][0]++, () => {})`
+    fn visit_mut_assign_pat(&mut self, pat: &mut AssignPat) {
+        let branch = self.new_branch("default-arg", &pat.span, &[pat.right.span()]);
+        pat.left.visit_mut_with(self);
+        if let Pat::Ident(binding) = &*pat.left {
+            self.hint_fn_name(&pat.right, Some(binding.id.sym.to_string()), Some(binding.id.span));
+        }
+        pat.right.visit_mut_with(self);
+        self.fn_name_hint = None;
+        if let Some(branch) = branch {
+            prepend_expr_counter(self.branch_counter(branch, 0), &mut pat.right);
+        }
+    }
+
+    /// 解构默认值 `const { cb = () => {} } = options`，同样计为 default-arg 分支
+    fn visit_mut_assign_pat_prop(&mut self, prop: &mut AssignPatProp) {
+        let branch = match &prop.value {
+            Some(value) => self.new_branch("default-arg", &prop.span, &[value.span()]),
+            None => None,
+        };
+        prop.key.visit_mut_with(self);
+        if let Some(value) = &prop.value {
+            self.hint_fn_name(value, Some(prop.key.sym.to_string()), Some(prop.key.span));
+        }
+        prop.value.visit_mut_with(self);
+        self.fn_name_hint = None;
+        if let (Some(branch), Some(value)) = (branch, &mut prop.value) {
+            prepend_expr_counter(self.branch_counter(branch, 0), value);
+        }
+    }
+
+    /// `export default function () {}` 命名为 default
+    fn visit_mut_export_default_decl(&mut self, decl: &mut ExportDefaultDecl) {
+        if let DefaultDecl::Fn(FnExpr { ident: None, .. }) = &decl.decl {
+            self.fn_name_hint = Some(("default".to_string(), None));
+        }
+        decl.visit_mut_children_with(self);
+        self.fn_name_hint = None;
+    }
+
+    /// `export default () => {}` 命名为 default
+    fn visit_mut_export_default_expr(&mut self, expr: &mut ExportDefaultExpr) {
+        self.hint_fn_name(&expr.expr, Some("default".to_string()), None);
+        expr.visit_mut_children_with(self);
+        self.fn_name_hint = None;
+    }
+
+    /// if 分支：两条路径的位置都取整个 if 语句（与 Istanbul 一致），没有 else 时补一个只含计数器的 else
+    fn visit_mut_if_stmt(&mut self, stmt: &mut IfStmt) {
+        ensure_block(&mut stmt.cons);
+        if let Some(alt) = &mut stmt.alt {
+            ensure_block(alt);
+        }
+        let branch = self.new_branch("if", &stmt.span, &[stmt.span, stmt.span]);
+        stmt.visit_mut_children_with(self);
+        let Some(branch) = branch else {
+            return;
+        };
+        if let Stmt::Block(block) = &mut *stmt.cons {
+            block.stmts.insert(0, self.branch_counter_stmt(branch, 0));
+        }
+        match &mut stmt.alt {
+            Some(alt) => {
+                if let Stmt::Block(block) = &mut **alt {
+                    block.stmts.insert(0, self.branch_counter_stmt(branch, 1));
+                }
+            }
+            None => {
+                stmt.alt = Some(Box::new(Stmt::Block(BlockStmt {
+                    span: DUMMY_SP,
+                    stmts: vec![self.branch_counter_stmt(branch, 1)],
+                    ..BlockStmt::dummy()
+                })));
+            }
+        }
+    }
+
+    /// 三元表达式：`test ? (cov().b[n][0]++, cons) : (cov().b[n][1]++, alt)`，
+    /// 在 JSX 表达式容器（子节点、属性值、fragment 子节点）中同样适用
+    fn visit_mut_cond_expr(&mut self, expr: &mut CondExpr) {
+        let branch = self.new_branch("cond-expr", &expr.span, &[expr.cons.span(), expr.alt.span()]);
+        expr.visit_mut_children_with(self);
+        if let Some(branch) = branch {
+            prepend_expr_counter(self.branch_counter(branch, 0), &mut expr.cons);
+            prepend_expr_counter(self.branch_counter(branch, 1), &mut expr.alt);
+        }
+    }
+
+    /// 逻辑表达式 `a && b || c`：整条链是一个 binary-expr 分支，每个叶子是一条路径，
+    /// `{items.length > 0 && <Table/>}` 变成 `{(cov().b[n][0]++, items.length > 0) && (cov().b[n][1]++, <Table/>)}`
+    ///
+    /// 逻辑赋值 `a ??= b` 同样是 binary-expr 分支：第一条路径记录求值次数，第二条记录右侧被求值（发生赋值）的次数，
+    /// 改写为 `(cov().b[n][0]++, a ??= (cov().b[n][1]++, b))`
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Bin(bin) if is_logical_op(bin.op) => {
+                let mut leaves = vec![];
+                collect_logical_leaves(expr, &mut leaves);
+                match self.new_branch("binary-expr", &expr.span(), &leaves) {
+                    Some(branch) => self.instrument_logical_leaves(expr, branch, &mut 0),
+                    None => expr.visit_mut_children_with(self),
+                }
+            }
+            Expr::Assign(assign) if is_logical_assign_op(assign.op) => {
+                let paths = [assign.left.span(), assign.right.span()];
+                let branch = self.new_branch("binary-expr", &assign.span, &paths);
+                expr.visit_mut_children_with(self);
+                if let (Some(branch), Expr::Assign(assign)) = (branch, &mut *expr) {
+                    prepend_expr_counter(self.branch_counter(branch, 1), &mut assign.right);
+                    prepend_expr_counter(self.branch_counter(branch, 0), expr);
+                }
+            }
+            _ => expr.visit_mut_children_with(self),
+        }
+    }
+
+    /// switch 分支：每个 case 一条路径，计数器放在 case 的第一条语句前
+    fn visit_mut_switch_stmt(&mut self, stmt: &mut SwitchStmt) {
+        let paths: Vec<Span> = stmt.cases.iter().map(|case| case.span).collect();
+        let branch = self.new_branch("switch", &stmt.span, &paths);
+        stmt.visit_mut_children_with(self);
+        if let Some(branch) = branch {
+            for (index, case) in stmt.cases.iter_mut().enumerate() {
+                case.cons.insert(0, self.branch_counter_stmt(branch, index as u32));
+            }
+        }
+    }
+
+    fn visit_mut_for_stmt(&mut self, stmt: &mut ForStmt) {
+        ensure_block(&mut stmt.body);
+        stmt.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_for_in_stmt(&mut self, stmt: &mut ForInStmt) {
+        ensure_block(&mut stmt.body);
+        stmt.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_for_of_stmt(&mut self, stmt: &mut ForOfStmt) {
+        ensure_block(&mut stmt.body);
+        stmt.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_while_stmt(&mut self, stmt: &mut WhileStmt) {
+        ensure_block(&mut stmt.body);
+        stmt.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_do_while_stmt(&mut self, stmt: &mut DoWhileStmt) {
+        ensure_block(&mut stmt.body);
+        stmt.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_with_stmt(&mut self, stmt: &mut WithStmt) {
+        ensure_block(&mut stmt.body);
+        stmt.visit_mut_children_with(self);
+    }
+}
+
+/// 插到函数体的指令序言之后
+fn insert_after_directives(stmts: &mut Vec<Stmt>, stmt: Stmt) {
+    let index = stmts.iter().take_while(|stmt| stmt.directive_continue()).count();
+    stmts.insert(index, stmt);
+}
+
+/// 匿名函数的 decl：函数起始的一个字符，与 Istanbul 一致
+fn anonymous_decl_span(span: Span) -> Span {
+    if span.is_dummy() {
+        return span;
+    }
+    Span::new(span.lo, span.lo + BytePos(1))
+}
+
+/// 只有类型、不生成运行时代码的声明：interface、type、declare 声明和 const enum
+fn is_type_only_decl(decl: &Decl) -> bool {
+    match decl {
+        Decl::TsInterface(_) | Decl::TsTypeAlias(_) => true,
+        Decl::Var(var) => var.declare,
+        Decl::Fn(f) => f.declare,
+        Decl::Class(class) => class.declare,
+        Decl::TsEnum(e) => e.declare || e.is_const,
+        Decl::TsModule(module) => module.declare,
+        Decl::Using(_) => false,
+        #[cfg(swc_ast_unknown)]
+        _ => false,
+    }
+}
+
+/// 普通 enum 会编译成立即执行的赋值代码，计为一条语句
+fn is_runtime_enum(decl: &Decl) -> bool {
+    matches!(decl, Decl::TsEnum(e) if !e.declare && !e.is_const)
+}
+
+/// 作为语句计数的声明：类声明（declare class 除外）和普通 enum；
+/// 函数声明会提升，istanbul 只登记函数计数器，这里同样不计；变量声明按声明项计数，见 visit_mut_var_declarator
+fn is_counted_decl(decl: &Decl) -> bool {
+    matches!(decl, Decl::Class(class) if !class.declare) || is_runtime_enum(decl)
+}
+
+/// 去掉括号和 TS 类型断言，推断函数名时看穿这些包装
+pub(super) fn unwrap_expr(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Paren(ParenExpr { expr, .. })
+        | Expr::TsAs(TsAsExpr { expr, .. })
+        | Expr::TsSatisfies(TsSatisfiesExpr { expr, .. })
+        | Expr::TsNonNull(TsNonNullExpr { expr, .. })
+        | Expr::TsTypeAssertion(TsTypeAssertion { expr, .. })
+        | Expr::TsConstAssertion(TsConstAssertion { expr, .. }) => unwrap_expr(expr),
+        _ => expr,
+    }
+}
+
+/// 方法名，计算属性名没有名字
+fn prop_name(key: &PropName) -> Option<String> {
+    match key {
+        PropName::Ident(ident) => Some(ident.sym.to_string()),
+        PropName::Str(s) => Some(s.value.to_string_lossy().into_owned()),
+        PropName::Num(n) => Some(n.value.to_string()),
+        PropName::BigInt(n) => Some(n.value.to_string()),
+        _ => None,
+    }
+}
+
+/// 把计数器放到表达式前面: (counter, expr)
+fn prepend_expr_counter(counter: Expr, expr: &mut Expr) {
+    let value = expr.take();
+    *expr = Expr::Paren(ParenExpr {
+        span: DUMMY_SP,
+        expr: Box::new(Expr::Seq(SeqExpr {
+            span: DUMMY_SP,
+            exprs: vec![Box::new(counter), Box::new(value)],
+        })),
+    });
+}
+
+/// 插桩生成的覆盖率函数：名为 cov_ 开头、函数体中有 `var gcv = "__coverage__"`，
+/// 或函数体中的对象带 `_coverageSchema` 标记（不依赖函数名）
+fn is_coverage_preamble(stmt: &Stmt) -> bool {
+    let Stmt::Decl(Decl::Fn(decl)) = stmt else {
+        return false;
+    };
+    let Some(body) = &decl.function.body else {
+        return false;
+    };
+    let is_cov_fn = decl.ident.sym.starts_with("cov_");
+    body.stmts.iter().any(|stmt| match stmt {
+        Stmt::Decl(Decl::Var(var)) => var.decls.iter().any(|declarator| match declarator.init.as_deref() {
+            Some(Expr::Lit(Lit::Str(s))) => is_cov_fn && s.value == *"__coverage__",
+            Some(Expr::Object(object)) => has_coverage_schema(object),
+            _ => false,
+        }),
+        _ => false,
+    })
+}
+
+fn has_coverage_schema(object: &ObjectLit) -> bool {
+    object.props.iter().any(|prop| {
+        let Some(KeyValueProp { key, .. }) = prop.as_prop().and_then(|prop| prop.as_key_value()) else {
+            return false;
+        };
+        match key {
+            PropName::Ident(key) => key.sym == *coverage_template::COVERAGE_SCHEMA_KEY,
+            PropName::Str(key) => key.value == *coverage_template::COVERAGE_SCHEMA_KEY,
+            _ => false,
+        }
+    })
+}
+
+/// 匿名函数（没有推断出名字）不参与匹配
+fn is_ignored_name(patterns: &[Regex], name: Option<&str>) -> bool {
+    name.is_some_and(|name| patterns.iter().any(|regex| regex.is_match(name)))
+}
+
+fn is_logical_op(op: BinaryOp) -> bool {
+    matches!(op, BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing)
+}
+
+fn is_logical_assign_op(op: AssignOp) -> bool {
+    matches!(op, AssignOp::AndAssign | AssignOp::OrAssign | AssignOp::NullishAssign)
+}
+
+fn is_logical_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Bin(bin) => is_logical_op(bin.op),
+        Expr::Paren(paren) => is_logical_expr(&paren.expr),
+        _ => false,
+    }
+}
+
+/// 和 Istanbul 一样把嵌套的逻辑表达式（含括号）展平，收集叶子的位置
+fn collect_logical_leaves(expr: &Expr, leaves: &mut Vec<Span>) {
+    match expr {
+        Expr::Bin(bin) if is_logical_op(bin.op) => {
+            collect_logical_leaves(&bin.left, leaves);
+            collect_logical_leaves(&bin.right, leaves);
+        }
+        Expr::Paren(paren) if is_logical_expr(&paren.expr) => collect_logical_leaves(&paren.expr, leaves),
+        _ => leaves.push(expr.span()),
+    }
+}
+
+/// 把非块的语句体包成 BlockStmt，计数器才能插在体内的语句前面
+fn ensure_block(body: &mut Box<Stmt>) {
+    if !matches!(**body, Stmt::Block(_)) {
+        let stmt = body.as_mut().take();
+        **body = Stmt::Block(BlockStmt {
+            span: DUMMY_SP,
+            stmts: vec![stmt],
+            ..BlockStmt::dummy()
+        });
+    }
 }
 
 /// 创建 coverage instrumentation visitor
 /// get_range: 将 Span 转为 Istanbul Range，无 source_map 时传入 |_| Range::default()
-pub fn create_coverage_instrumentation_visitor<F>(filename: &str, get_range: F) -> CoverageVisitor
+///
+/// 不传注释时不识别 `@generated` 文件，需要时用 create_coverage_instrumentation_visitor_with_comments
+pub fn create_coverage_instrumentation_visitor<F>(filename: &str, options: InstrumentOptions, get_range: F) -> CoverageVisitor
 where
     F: Fn(&Span) -> Range + Send + Sync + 'static,
 {
-    CoverageVisitor::new(filename.to_string(), Box::new(get_range))
+    CoverageVisitor::new(filename.to_string(), options, Box::new(get_range), None)
+}
+
+/// 同 create_coverage_instrumentation_visitor，额外传入源码注释用于识别 `@generated` 文件，
+/// 插件中传入 metadata.comments
+pub fn create_coverage_instrumentation_visitor_with_comments<F, C>(
+    filename: &str,
+    options: InstrumentOptions,
+    get_range: F,
+    comments: C,
+) -> CoverageVisitor
+where
+    F: Fn(&Span) -> Range + Send + Sync + 'static,
+    C: Comments + 'static,
+{
+    CoverageVisitor::new(filename.to_string(), options, Box::new(get_range), Some(Box::new(comments)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use pretty_assertions::assert_eq;
+    use swc_core::common::{sync::Lrc, FileName, Globals, SourceMap, GLOBALS};
+    use swc_core::ecma::codegen::{text_writer::JsWriter, Emitter};
+    use swc_core::ecma::parser::{parse_file_as_program, EsSyntax, Syntax, TsSyntax};
+
+    use super::*;
+
+    struct Output {
+        cov: SourceCoverage,
+        /// 去掉初始化函数后的代码，cov_xxx 统一换成 cov
+        code: String,
+        /// 完整输出
+        full: String,
+    }
+
+    impl Output {
+        fn statements(&self) -> Vec<(u32, u32, u32, u32)> {
+            self.cov
+                .statement_map
+                .values()
+                .map(|r| (r.start.line, r.start.column, r.end.line, r.end.column))
+                .collect()
+        }
+
+        fn function_names(&self) -> Vec<&str> {
+            self.cov.fn_map.values().map(|f| f.name.as_str()).collect()
+        }
+
+        fn branches(&self) -> Vec<(&str, usize)> {
+            self.cov
+                .branch_map
+                .values()
+                .map(|b| (b.branch_type.as_str(), b.locations.len()))
+                .collect()
+        }
+    }
+
+    fn instrument(source: &str, options: &str) -> Output {
+        instrument_file("src/test.js", source, options, 1)
+    }
+
+    /// 解析 -> 插桩 passes 次（模拟插件在同一个 AST 上运行多次）-> 生成代码
+    fn instrument_file(filename: &str, source: &str, options: &str, passes: usize) -> Output {
+        let cm: Lrc<SourceMap> = Default::default();
+        let fm = cm.new_source_file(Lrc::new(FileName::Custom(filename.into())), source.to_string());
+        let syntax = if filename.ends_with(".ts") {
+            Syntax::Typescript(TsSyntax::default())
+        } else {
+            Syntax::Es(EsSyntax { jsx: true, decorators: true, explicit_resource_management: true, ..Default::default() })
+        };
+        let mut program =
+            parse_file_as_program(&fm, syntax, EsVersion::latest(), None, &mut vec![]).expect("parse");
+
+        let text = Arc::new(source.to_string());
+        let base = fm.start_pos.0;
+        let mut cov = SourceCoverage::new();
+        let mut cov_fn = String::new();
+        GLOBALS.set(&Globals::new(), || {
+            for _ in 0..passes {
+                let text = text.clone();
+                let position = move |pos: u32| {
+                    let before = &text[..(pos - base) as usize];
+                    let line = before.matches('\n').count() as u32 + 1;
+                    let column = before.rsplit('\n').next().unwrap_or_default().chars().count() as u32;
+                    (line, column)
+                };
+                let get_range = move |span: &Span| {
+                    if span.is_dummy() {
+                        return Range::default();
+                    }
+                    let (start_line, start_column) = position(span.lo.0);
+                    let (end_line, end_column) = position(span.hi.0);
+                    Range::new(start_line, start_column, end_line, end_column)
+                };
+                let mut visitor =
+                    create_coverage_instrumentation_visitor(filename, serde_json::from_str(options).unwrap(), get_range);
+                program.visit_mut_with(&mut visitor);
+                cov = visitor.cov.into_inner();
+                cov_fn = visitor.cov_fn_ident.sym.to_string();
+            }
+        });
+
+        let mut buf = vec![];
+        Emitter {
+            cfg: Default::default(),
+            cm: cm.clone(),
+            comments: None,
+            wr: JsWriter::new(cm, "\n", &mut buf, None),
+        }
+        .emit_program(&program)
+        .unwrap();
+        let full = String::from_utf8(buf).unwrap();
+        let code = match full.split_once(&format!("\n{cov_fn}();\n")) {
+            Some((_, code)) => code.to_string(),
+            None => full.clone(),
+        };
+        Output { cov, code: code.replace(&cov_fn, "cov"), full }
+    }
+
+    #[test]
+    fn class_declarations_are_statements() {
+        let out = instrument_file(
+            "src/test.js",
+            "class A {}\nexport class B {}\nexport default class {}\nfunction f() {}\n",
+            "{}",
+            1,
+        );
+        assert_eq!(out.statements(), vec![(1, 0, 1, 10), (2, 0, 2, 17), (3, 0, 3, 23)]);
+        assert_eq!(out.function_names(), vec!["f"]);
+        assert!(out.code.starts_with("cov().s[0]++;\nclass A {\n}\ncov().s[1]++;\nexport class B {\n}\n"), "{}", out.code);
+    }
+
+    #[test]
+    fn single_statement_bodies_are_wrapped() {
+        let out = instrument("if (a) b(); else c();\nwhile (x) step();\n", "{}");
+        assert_eq!(out.statements().len(), 5);
+        assert_eq!(
+            out.code,
+            "cov().s[0]++;\nif (a) {\n    cov().b[0][0]++;\n    cov().s[1]++;\n    b();\n} else {\n    cov().b[0][1]++;\n    cov().s[2]++;\n    c();\n}\ncov().s[3]++;\nwhile(x){\n    cov().s[4]++;\n    step();\n}\n"
+        );
+    }
+
+    #[test]
+    fn declarators_and_class_fields_are_statements() {
+        let out = instrument("let a = 1, b;\nclass C { x = 2; static y; }\n", "{}");
+        // 有初始值的 declarator、class 声明、x 的初始值；没有初始值的不计
+        assert_eq!(out.statements(), vec![(1, 4, 1, 9), (2, 0, 2, 28), (2, 14, 2, 15)]);
+        assert!(out.code.starts_with("let a = (cov().s[0]++, 1), b;\n"), "{}", out.code);
+    }
+
+    #[test]
+    fn concise_arrow_body_is_rewritten() {
+        let out = instrument("const f = (x) => ({ x });\n", "{}");
+        assert_eq!(out.function_names(), vec!["f"]);
+        assert_eq!(out.statements().len(), 2);
+        assert!(
+            out.code.contains("=>{\n    cov().f[0]++;\n    cov().s[1]++;\n    return ({\n        x\n    });\n}"),
+            "{}",
+            out.code
+        );
+    }
+
+    #[test]
+    fn anonymous_functions_are_named_from_context() {
+        let out = instrument(
+            "const handler = () => {};\nconst o = { key: function () {}, m() {} };\nclass K { run() {} #p = () => {}; }\n[1].map(function () {});\nexport default () => {};\n",
+            "{}",
+        );
+        assert_eq!(out.function_names(), vec!["handler", "key", "m", "run", "#p", "(anonymous_5)", "default"]);
+    }
+
+    #[test]
+    fn private_prop_name_is_not_given_to_decorators() {
+        let out = instrument("class P { @dec(() => {}) #x = () => {}; }\n", "{}");
+        assert_eq!(out.function_names(), vec!["(anonymous_0)", "#x"]);
+    }
+
+    #[test]
+    fn export_default_expression_is_a_statement() {
+        let out = instrument("export default a + b;\n", "{}");
+        assert_eq!(out.statements(), vec![(1, 0, 1, 21)]);
+        assert_eq!(out.code, "cov().s[0]++;\nexport default a + b;\n");
+    }
+
+    #[test]
+    fn typescript_type_only_syntax_is_skipped() {
+        let out = instrument_file(
+            "src/test.ts",
+            "interface I { a: string }\ntype T = string;\ndeclare const d: number;\ndeclare class D {}\nexport type { I };\nfunction o(a: string): void;\nfunction o(a: any) {}\nenum E { A }\nconst y: T = \"\";\n",
+            "{}",
+            1,
+        );
+        // 只有 enum 和 y 的 declarator
+        assert_eq!(out.statements(), vec![(8, 0, 8, 12), (9, 6, 9, 15)]);
+        assert_eq!(out.function_names(), vec!["o"]);
+    }
+
+    #[test]
+    fn if_branch_locations() {
+        let out = instrument("if (a) b();\n", "{}");
+        let branch = &out.cov.branch_map[&0];
+        assert_eq!(branch.branch_type, "if");
+        // 和 istanbul 一样，两条路径的位置都取 if 语句
+        assert_eq!(branch.locations, vec![branch.loc, branch.loc]);
+        assert!(out.code.contains("} else {\n    cov().b[0][1]++;\n}"), "{}", out.code);
+    }
+
+    #[test]
+    fn conditional_and_logical_branches() {
+        let out = instrument("x = a ? b : c;\ny = a && b || c;\nz ??= d;\n", "{}");
+        assert_eq!(out.branches(), vec![("cond-expr", 2), ("binary-expr", 3), ("binary-expr", 2)]);
+        let cond = &out.cov.branch_map[&0];
+        assert_eq!(
+            cond.locations.iter().map(|r| (r.start.column, r.end.column)).collect::<Vec<_>>(),
+            vec![(8, 9), (12, 13)]
+        );
+        assert!(out.code.contains("a ? (cov().b[0][0]++, b) : (cov().b[0][1]++, c)"), "{}", out.code);
+        assert!(
+            out.code.contains("(cov().b[1][0]++, a) && (cov().b[1][1]++, b) || (cov().b[1][2]++, c)"),
+            "{}",
+            out.code
+        );
+    }
+
+    #[test]
+    fn switch_and_default_arg_branches() {
+        let out = instrument("function f(a = 1) {\n  switch (a) {\n    case 1: g();\n    default: h();\n  }\n}\n", "{}");
+        assert_eq!(out.branches(), vec![("default-arg", 1), ("switch", 2)]);
+        assert!(out.code.contains("case 1:\n            cov().b[1][0]++;"), "{}", out.code);
+        assert!(out.code.contains("default:\n            cov().b[1][1]++;"), "{}", out.code);
+    }
+
+    #[test]
+    fn jsx_branches_stay_valid_jsx() {
+        let out = instrument("const el = <div>{ok ? <A /> : <B />}{list && <T />}</div>;\n", "{}");
+        assert_eq!(out.branches(), vec![("cond-expr", 2), ("binary-expr", 2)]);
+        let syntax = Syntax::Es(EsSyntax { jsx: true, ..Default::default() });
+        let cm: Lrc<SourceMap> = Default::default();
+        let fm = cm.new_source_file(Lrc::new(FileName::Anon), out.full);
+        assert!(parse_file_as_program(&fm, syntax, EsVersion::latest(), None, &mut vec![]).is_ok());
+    }
+
+    #[test]
+    fn static_blocks_are_functions() {
+        let out = instrument("class S { static { init(); } }\n", "{}");
+        assert_eq!(out.function_names().len(), 1);
+        assert_eq!(out.statements().len(), 2);
+    }
+
+    #[test]
+    fn known_helpers_are_skipped() {
+        let out = instrument("var _react = _interop_require_default(require(\"react\"));\nrun();\n", "{}");
+        assert_eq!(out.statements(), vec![(2, 0, 2, 6)]);
+    }
+
+    #[test]
+    fn ignored_function_names() {
+        let out = instrument(
+            "function logDebug() { a(); }\nclass C { helper() { b(); } run() {} }\n",
+            r#"{ "ignoreFunctionNames": ["^log"], "ignoreClassMethods": ["^helper$"] }"#,
+        );
+        assert_eq!(out.function_names(), vec!["run"]);
+        assert!(!out.code.contains("a();\n    cov()"), "{}", out.code);
+    }
+
+    #[test]
+    fn changed_lines_limit_registration() {
+        let out = instrument("a();\nb();\nc();\n", r#"{ "changedLines": { "src/test.js": [2] } }"#);
+        assert_eq!(out.statements(), vec![(2, 0, 2, 4)]);
+    }
+
+    #[test]
+    fn unchanged_file_is_not_instrumented() {
+        let out = instrument("a();\n", r#"{ "changedLines": { "src/other.js": [1] } }"#);
+        assert_eq!(out.full, "a();\n");
+    }
+
+    #[test]
+    fn functions_mode_only_counts_functions() {
+        let out = instrument("function f(a) { return a ? 1 : 2; }\n", r#"{ "instrumentMode": "functions" }"#);
+        assert_eq!(out.function_names(), vec!["f"]);
+        assert!(out.cov.statement_map.is_empty() && out.cov.branch_map.is_empty());
+        assert_eq!(out.code, "function f(a) {\n    cov().f[0]++;\n    return a ? 1 : 2;\n}\n");
+    }
+
+    #[test]
+    fn boolean_hit_mode_sets_counters_once() {
+        let out = instrument("function f(a) { return a || b; }\n", r#"{ "hitMode": "boolean" }"#);
+        assert_eq!(
+            out.code,
+            "function f(a) {\n    cov().f[0] = 1;\n    cov().s[0] = 1;\n    return (cov().b[0][0] = 1, a) || (cov().b[0][1] = 1, b);\n}\n"
+        );
+    }
+
+    #[test]
+    fn directives_stay_before_the_preamble() {
+        let out = instrument("\"use client\";\na();\n", "{}");
+        assert!(out.full.starts_with("\"use client\";\nfunction cov_"), "{}", out.full);
+    }
+
+    #[test]
+    fn running_twice_keeps_a_single_preamble() {
+        let once = instrument("function f() { a(); }\n", "{}");
+        let twice = instrument_file("src/test.js", "function f() { a(); }\n", "{}", 2);
+        assert_eq!(twice.full.matches("function cov_").count(), 1);
+        assert_eq!(twice.full, once.full);
+        assert!(twice.cov.fn_map.is_empty());
+    }
 }
@@ -1 +1,2 @@
 pub mod coverage_visitor;
+mod skip_list;
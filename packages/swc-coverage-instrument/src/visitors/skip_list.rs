@@ -0,0 +1,207 @@
+//! 其它转换生成的代码（辅助函数、React Refresh 注册、HMR 代码块）不属于源码，插桩后只会变成未覆盖的噪音，
+//! 按名字识别后整条语句跳过
+//!
+//! 参与匹配的名字：
+//! - 函数 / 类声明的名字，变量声明的名字或初始值调用的函数（所有声明项都匹配时才跳过）
+//! - 表达式语句中调用的函数（`a.b.c(...)` 取 `a.b.c`），赋值语句的左侧变量或右侧调用的函数
+//! - if 语句的条件，如 `if (import.meta.hot) { ... }`
+//!
+//! `_c`、`_s`、`_extends` 这类短名字用户代码里也会出现，只在文件确实是对应产物时才生效，见 [`FileMarkers`]
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use swc_core::ecma::{
+    ast::*,
+    visit::{Visit, VisitWith},
+};
+
+use super::coverage_visitor::unwrap_expr;
+
+/// 内置的跳过规则，任何文件都生效
+const KNOWN_PATTERNS: [&str; 3] = [
+    // swc / babel 内联的辅助函数：_interop_require_default、_classCallCheck 等
+    r"^_(interop_require_(default|wildcard)|interopRequire(Default|Wildcard)|class_call_check|classCallCheck|create_class|createClass|define_property|defineProperty|async_to_generator|asyncToGenerator|object_spread(_props)?|objectSpread2?|object_without_properties(_loose)?|objectWithoutProperties(Loose)?|sliced_to_array|slicedToArray|to_consumable_array|toConsumableArray|create_super|createSuper|call_super|callSuper|get_prototype_of|getPrototypeOf|set_prototype_of|setPrototypeOf|possible_constructor_return|possibleConstructorReturn|assert_this_initialized|assertThisInitialized|export_star|exportStar|ts_decorate|ts_metadata|ts_param|ts_generator|ts_values|class_private_field_(get|set|init|loose_base|loose_key)|classPrivateField(Get|Set|Init|LooseBase|LooseKey)|class_private_method_(get|init)|classPrivateMethod(Get|Init)|tagged_template_literal(_loose)?|taggedTemplateLiteral(Loose)?|using_ctx|usingCtx)$",
+    // TypeScript tslib 风格的辅助函数：__awaiter、__decorate 等
+    r"^__(awaiter|generator|decorate|metadata|param|assign|rest|extends|importDefault|importStar|createBinding|setModuleDefault|exportStar|values|read|spread|spreadArray|spreadArrays|await|asyncGenerator|asyncDelegator|asyncValues|makeTemplateObject|classPrivateField(Get|Set|In)|esDecorate|runInitializers|propKey|setFunctionName|addDisposableResource|disposeResources|rewriteRelativeImportExtension)$",
+    // Vite / webpack 的 HMR 代码：if (import.meta.hot) {...}、module.hot.accept()
+    r"^(import\.meta\.(hot|webpackHot)|module\.hot)(\.|$)",
+];
+
+/// 名字太常见的辅助函数，只在转译产物中跳过
+const TRANSPILED_PATTERN: &str = r"^_(extends|export|jsx|jsxs|typeof|type_of|inherits)$";
+
+/// React Refresh 注册：$RefreshReg$(_c, "App")、var _s = $RefreshSig$()、_s(App, "...")、_c = App，
+/// 只在引用了 $RefreshReg$ / $RefreshSig$ 的文件中跳过
+const REACT_REFRESH_PATTERN: &str = r"^(\$RefreshReg\$|\$RefreshSig\$|_s\d*|_c\d*)$";
+
+/// 辅助函数所在的包，import / require 这些包说明文件是转译产物
+const HELPER_PACKAGES: [&str; 3] = ["@swc/helpers", "@babel/runtime", "tslib"];
+
+static KNOWN_REGEXES: Lazy<Vec<Regex>> =
+    Lazy::new(|| KNOWN_PATTERNS.iter().map(|p| Regex::new(p).expect("invalid built-in pattern")).collect());
+static TRANSPILED_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(TRANSPILED_PATTERN).expect("invalid built-in pattern"));
+static REACT_REFRESH_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(REACT_REFRESH_PATTERN).expect("invalid built-in pattern"));
+
+/// 文件中能说明它是其它转换产物的痕迹
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FileMarkers {
+    /// 引用了 `$RefreshReg$` / `$RefreshSig$`
+    pub react_refresh: bool,
+    /// import / require 了辅助函数包，或有 `Object.defineProperty(exports, "__esModule", ...)`
+    pub transpiled: bool,
+}
+
+impl FileMarkers {
+    pub fn scan(program: &Program) -> Self {
+        let mut markers = Self::default();
+        program.visit_with(&mut markers);
+        markers
+    }
+}
+
+fn is_helper_package(src: &str) -> bool {
+    HELPER_PACKAGES
+        .iter()
+        .any(|pkg| src.strip_prefix(pkg).is_some_and(|rest| rest.is_empty() || rest.starts_with('/')))
+}
+
+impl Visit for FileMarkers {
+    fn visit_ident(&mut self, ident: &Ident) {
+        if &*ident.sym == "$RefreshReg$" || &*ident.sym == "$RefreshSig$" {
+            self.react_refresh = true;
+        }
+    }
+
+    fn visit_import_decl(&mut self, import: &ImportDecl) {
+        if is_helper_package(&import.src.value.to_string_lossy()) {
+            self.transpiled = true;
+        }
+        import.visit_children_with(self);
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let Callee::Expr(callee) = &call.callee {
+            let first_arg = call.args.first().map(|arg| unwrap_expr(&arg.expr));
+            match (expr_path(callee).as_deref(), first_arg) {
+                (Some("require"), Some(Expr::Lit(Lit::Str(src)))) if is_helper_package(&src.value.to_string_lossy()) => {
+                    self.transpiled = true;
+                }
+                (Some("Object.defineProperty"), Some(Expr::Ident(target))) if &*target.sym == "exports" => {
+                    self.transpiled = true;
+                }
+                _ => {}
+            }
+        }
+        call.visit_children_with(self);
+    }
+}
+
+/// 内置规则加上配置中的 ignorePatterns
+pub struct SkipList {
+    patterns: Vec<Regex>,
+    markers: FileMarkers,
+}
+
+impl SkipList {
+    pub fn new(user_patterns: &[String]) -> Self {
+        Self { patterns: compile_patterns("ignorePatterns", user_patterns), markers: FileMarkers::default() }
+    }
+
+    /// 处理文件前调用，决定短名字规则是否生效
+    pub fn set_markers(&mut self, markers: FileMarkers) {
+        self.markers = markers;
+    }
+
+    fn is_match(&self, name: &str) -> bool {
+        KNOWN_REGEXES.iter().chain(&self.patterns).any(|regex| regex.is_match(name))
+            || (self.markers.transpiled && TRANSPILED_REGEX.is_match(name))
+            || (self.markers.react_refresh && REACT_REFRESH_REGEX.is_match(name))
+    }
+
+    fn is_match_path(&self, expr: &Expr) -> bool {
+        expr_path(expr).is_some_and(|path| self.is_match(&path))
+    }
+
+    /// 调用的函数是否匹配，包括 `a?.b()`
+    fn is_match_call(&self, expr: &Expr) -> bool {
+        match unwrap_expr(expr) {
+            Expr::Call(CallExpr { callee: Callee::Expr(callee), .. }) => self.is_match_path(callee),
+            Expr::OptChain(OptChainExpr { base, .. }) => {
+                matches!(&**base, OptChainBase::Call(call) if self.is_match_path(&call.callee))
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_skipped_decl(&self, decl: &Decl) -> bool {
+        match decl {
+            Decl::Fn(decl) => self.is_match(&decl.ident.sym),
+            Decl::Class(decl) => self.is_match(&decl.ident.sym),
+            Decl::Var(var) => {
+                !var.decls.is_empty()
+                    && var.decls.iter().all(|declarator| {
+                        matches!(&declarator.name, Pat::Ident(name) if self.is_match(&name.sym))
+                            || declarator.init.as_deref().is_some_and(|init| self.is_match_call(init))
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_skipped_stmt(&self, stmt: &Stmt) -> bool {
+        match stmt {
+            Stmt::Decl(decl) => self.is_skipped_decl(decl),
+            Stmt::Expr(ExprStmt { expr, .. }) => match unwrap_expr(expr) {
+                Expr::Assign(assign) => {
+                    matches!(&assign.left, AssignTarget::Simple(SimpleAssignTarget::Ident(name)) if self.is_match(&name.sym))
+                        || self.is_match_call(&assign.right)
+                }
+                expr => self.is_match_call(expr),
+            },
+            Stmt::If(stmt) => self.is_match_path(&stmt.test),
+            _ => false,
+        }
+    }
+}
+
+/// 编译配置中的正则，无效的项打印提示后忽略
+pub fn compile_patterns(option: &str, patterns: &[String]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .filter_map(|pattern| match Regex::new(pattern) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                println!("  -> 忽略无效的 {option} 项 {pattern:?}: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// `a.b.c` / `a?.b` / `import.meta.hot` 转成点分路径，其它表达式返回 None
+fn expr_path(expr: &Expr) -> Option<String> {
+    match unwrap_expr(expr) {
+        Expr::Ident(ident) => Some(ident.sym.to_string()),
+        Expr::This(_) => Some("this".to_string()),
+        Expr::MetaProp(MetaPropExpr { kind: MetaPropKind::ImportMeta, .. }) => Some("import.meta".to_string()),
+        Expr::Member(member) => member_path(&member.obj, &member.prop),
+        Expr::OptChain(OptChainExpr { base, .. }) => match &**base {
+            OptChainBase::Member(member) => member_path(&member.obj, &member.prop),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn member_path(obj: &Expr, prop: &MemberProp) -> Option<String> {
+    let prop = match prop {
+        MemberProp::Ident(ident) => ident.sym.to_string(),
+        MemberProp::Computed(ComputedPropName { expr, .. }) => match &**expr {
+            Expr::Lit(Lit::Str(s)) => s.value.to_string_lossy().into_owned(),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(format!("{}.{prop}", expr_path(obj)?))
+}
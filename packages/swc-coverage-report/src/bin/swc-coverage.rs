@@ -0,0 +1,276 @@
+//! swc-coverage 命令行：
+//! - `swc-coverage report coverage/coverage-final.json -r text -r text-summary`
+//! - `swc-coverage check-coverage coverage/ --lines 80 --branches -10 --per-file`
+//! - `swc-coverage serve --port 7788 --out-dir coverage`
+//! - `git diff -U0 origin/main | swc-coverage changed-lines - -o changed-lines.json`
+
+use std::io::{IsTerminal, Read};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use swc_coverage_instrument::parse_unified_diff;
+use swc_coverage_report::reporters::json_summary::{write_json_summary, JSON_SUMMARY_FILE};
+use swc_coverage_report::reporters::text::{text_report, TextReportOptions};
+use swc_coverage_report::reporters::text_summary::{text_summary_report, TextSummaryOptions};
+use swc_coverage_report::{
+    check_coverage, serve, Collector, CoverageMap, ServeOptions, ThresholdConfig, Thresholds, DEFAULT_BUILD,
+};
+
+#[derive(Parser)]
+#[command(name = "swc-coverage", about = "Istanbul coverage reports and threshold checks")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 合并覆盖率文件并输出报告
+    Report {
+        /// coverage-final.json 文件或包含 json 文件的目录
+        #[arg(default_value = "coverage/coverage-final.json")]
+        inputs: Vec<PathBuf>,
+        #[arg(short, long = "reporter", value_enum, default_values_t = [Reporter::Text])]
+        reporters: Vec<Reporter>,
+        /// json-summary 等文件型报告的输出目录
+        #[arg(long, default_value = "coverage")]
+        report_dir: PathBuf,
+        /// 按 inputSourceMap 或外部 .map 文件映射回原始文件
+        #[arg(long)]
+        source_maps: bool,
+    },
+    /// 检查覆盖率阈值，未达标时以退出码 1 结束
+    CheckCoverage {
+        #[arg(default_value = "coverage/coverage-final.json")]
+        inputs: Vec<PathBuf>,
+        #[arg(long, allow_negative_numbers = true)]
+        statements: Option<f64>,
+        #[arg(long, allow_negative_numbers = true)]
+        branches: Option<f64>,
+        #[arg(long, allow_negative_numbers = true)]
+        functions: Option<f64>,
+        #[arg(long, allow_negative_numbers = true)]
+        lines: Option<f64>,
+        /// 对每个文件单独检查命令行给出的阈值
+        #[arg(long)]
+        per_file: bool,
+        /// 阈值配置文件（json），命令行阈值优先
+        #[arg(long)]
+        config: Option<PathBuf>,
+        #[arg(long)]
+        source_maps: bool,
+    },
+    /// 启动本地收集服务，接收插件 upload 上报的覆盖率并按构建合并
+    Serve {
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        #[arg(short, long, default_value_t = 7788)]
+        port: u16,
+        /// 报告输出目录，每个构建写到 `<out-dir>/<build>/`
+        #[arg(long, default_value = "coverage")]
+        out_dir: PathBuf,
+        /// 每次上报后立即写出报告，默认只在 `POST /reports` 时写出
+        #[arg(long)]
+        write_on_upload: bool,
+        /// 启动时先载入已有的覆盖率文件，归入默认构建
+        #[arg(long)]
+        preload: Vec<PathBuf>,
+    },
+    /// 把 unified diff 转成插件增量插桩的 changedLines 配置
+    ChangedLines {
+        /// diff 文件，`-` 表示从标准输入读取
+        diff: PathBuf,
+        /// 输出文件，默认输出到标准输出
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Reporter {
+    Text,
+    TextSummary,
+    JsonSummary,
+}
+
+fn read_json_files(path: &Path) -> Result<Vec<PathBuf>, String> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let entries = std::fs::read_dir(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        // 同目录下的 json-summary 输出不是覆盖率数据
+        .filter(|p| p.file_name().is_none_or(|name| name != JSON_SUMMARY_FILE))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// 读取并合并所有输入，需要时按 source map 映射
+fn load_coverage(inputs: &[PathBuf], source_maps: bool) -> Result<CoverageMap, String> {
+    let mut map = CoverageMap::new();
+    for input in inputs {
+        for file in read_json_files(input)? {
+            let content = std::fs::read_to_string(&file).map_err(|e| format!("{}: {e}", file.display()))?;
+            let coverage = CoverageMap::from_json(&content).map_err(|e| format!("{}: {e}", file.display()))?;
+            map.merge(&coverage);
+        }
+    }
+    if source_maps {
+        map = map.remap_source_maps().map_err(|e| e.to_string())?;
+    }
+    Ok(map)
+}
+
+fn report(
+    inputs: &[PathBuf],
+    reporters: &[Reporter],
+    report_dir: &Path,
+    source_maps: bool,
+) -> Result<ExitCode, String> {
+    let map = load_coverage(inputs, source_maps)?;
+    let color = std::io::stdout().is_terminal();
+    for reporter in reporters {
+        match reporter {
+            Reporter::Text => print!(
+                "{}",
+                text_report(&map, &TextReportOptions { color, ..Default::default() })
+            ),
+            Reporter::TextSummary => print!(
+                "{}",
+                text_summary_report(&map, &TextSummaryOptions { color, ..Default::default() })
+            ),
+            Reporter::JsonSummary => {
+                write_json_summary(&map, report_dir).map_err(|e| format!("{}: {e}", report_dir.display()))?
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn check(
+    inputs: &[PathBuf],
+    cli_thresholds: Thresholds,
+    per_file: bool,
+    config: Option<&Path>,
+    source_maps: bool,
+) -> Result<ExitCode, String> {
+    let map = load_coverage(inputs, source_maps)?;
+    let mut config: ThresholdConfig = match config {
+        Some(path) => {
+            let content = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+            serde_json::from_str(&content).map_err(|e| format!("{}: {e}", path.display()))?
+        }
+        None => ThresholdConfig::default(),
+    };
+    let target = if per_file {
+        config.per_file.get_or_insert_with(Thresholds::default)
+    } else {
+        &mut config.global
+    };
+    target.statements = cli_thresholds.statements.or(target.statements);
+    target.branches = cli_thresholds.branches.or(target.branches);
+    target.functions = cli_thresholds.functions.or(target.functions);
+    target.lines = cli_thresholds.lines.or(target.lines);
+    config.root = std::env::current_dir()
+        .ok()
+        .map(|dir| dir.to_string_lossy().replace('\\', "/"));
+
+    let violations = check_coverage(&map, &config).map_err(|e| e.to_string())?;
+    for violation in &violations {
+        eprintln!("{violation}");
+    }
+    Ok(if violations.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn start_server(
+    host: &str,
+    port: u16,
+    out_dir: PathBuf,
+    write_on_upload: bool,
+    preload: &[PathBuf],
+) -> Result<ExitCode, String> {
+    let mut collector = Collector::new();
+    if !preload.is_empty() {
+        collector.add(DEFAULT_BUILD, &load_coverage(preload, false)?);
+    }
+    let listener = TcpListener::bind((host, port)).map_err(|e| format!("{host}:{port}: {e}"))?;
+    let addr = listener.local_addr().map_err(|e| e.to_string())?;
+    println!("swc-coverage collector listening on http://{addr}/coverage/client");
+    let options = ServeOptions {
+        out_dir,
+        write_on_upload,
+    };
+    serve(listener, &mut collector, &options).map_err(|e| e.to_string())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn changed_lines(diff: &Path, output: Option<&Path>) -> Result<ExitCode, String> {
+    let text = if diff == Path::new("-") {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text).map_err(|e| e.to_string())?;
+        text
+    } else {
+        std::fs::read_to_string(diff).map_err(|e| format!("{}: {e}", diff.display()))?
+    };
+    let config = serde_json::json!({ "changedLines": parse_unified_diff(&text) });
+    let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    match output {
+        Some(output) => std::fs::write(output, json).map_err(|e| format!("{}: {e}", output.display()))?,
+        None => println!("{json}"),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Report {
+            inputs,
+            reporters,
+            report_dir,
+            source_maps,
+        } => report(&inputs, &reporters, &report_dir, source_maps),
+        Command::CheckCoverage {
+            inputs,
+            statements,
+            branches,
+            functions,
+            lines,
+            per_file,
+            config,
+            source_maps,
+        } => check(
+            &inputs,
+            Thresholds {
+                statements,
+                branches,
+                functions,
+                lines,
+            },
+            per_file,
+            config.as_deref(),
+            source_maps,
+        ),
+        Command::Serve {
+            host,
+            port,
+            out_dir,
+            write_on_upload,
+            preload,
+        } => start_server(&host, port, out_dir, write_on_upload, &preload),
+        Command::ChangedLines { diff, output } => changed_lines(&diff, output.as_deref()),
+    };
+    result.unwrap_or_else(|message| {
+        eprintln!("swc-coverage: {message}");
+        ExitCode::from(2)
+    })
+}
@@ -0,0 +1,308 @@
+//! 本地覆盖率收集：接收浏览器上报的覆盖率，按构建合并，需要时写出 coverage-final.json 和报告
+//!
+//! 上报体支持两种格式：
+//! - 原始的 `window.__coverage__` 对象
+//! - Canyon 信封 `{ "sha": "...", "buildID": "...", "sessionId": "...", ..., "coverage": { ... } }`（见插件的 upload 配置）
+//!
+//! 同一页面多次上报的是页面加载以来的累计计数：带 sessionId 的上报替换该会话上一次的数据
+//! （从合并结果中减去上一次的计数再加上新的），没有 sessionId 的上报视为独立的一次运行，直接累加。
+//! 每个构建最多保留 MAX_SESSIONS 个会话，超过时丢弃最久没有上报的会话，它最后一次的数据留在合并结果中
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use serde_json::Value;
+
+use crate::coverage_map::CoverageMap;
+use crate::reporters::json_summary::write_json_summary;
+
+pub const COVERAGE_FINAL_FILE: &str = "coverage-final.json";
+
+/// 未带构建信息的上报归入该构建
+pub const DEFAULT_BUILD: &str = "default";
+
+/// 每个构建最多保留的会话数
+const MAX_SESSIONS: usize = 1000;
+
+/// 按构建区分覆盖率的字段，取第一个有值的
+const BUILD_KEYS: [&str; 2] = ["buildID", "sha"];
+
+fn build_key_of(meta: &serde_json::Map<String, Value>) -> Option<String> {
+    BUILD_KEYS.iter().find_map(|key| match meta.get(*key)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+/// 构建名用作目录名，去掉路径分隔符等字符；替换过字符时加上原名的 hash，避免 `feat/x` 和 `feat_x` 写到同一个目录
+fn sanitize(build: &str) -> String {
+    let sanitized: String = build
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    if sanitized == build && !build.chars().all(|c| c == '.') {
+        return sanitized;
+    }
+    let mut hasher = DefaultHasher::new();
+    build.hash(&mut hasher);
+    format!("{sanitized}-{:08x}", hasher.finish() as u32)
+}
+
+/// 一次上报
+#[derive(Clone, Debug, PartialEq)]
+pub struct Payload {
+    pub build: String,
+    /// 上报运行时为每个页面生成的会话 id，原始 __coverage__ 没有
+    pub session: Option<String>,
+    pub coverage: CoverageMap,
+}
+
+/// 解析一次上报
+pub fn parse_payload(body: &str) -> serde_json::Result<Payload> {
+    let mut value: Value = serde_json::from_str(body)?;
+    let envelope = value
+        .as_object_mut()
+        .filter(|obj| obj.get("coverage").is_some_and(Value::is_object))
+        .map(|obj| {
+            let coverage = obj.remove("coverage").unwrap_or_default();
+            let session = obj
+                .get("sessionId")
+                .and_then(Value::as_str)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string);
+            (build_key_of(obj), session, coverage)
+        });
+    let (build, session, coverage) = match envelope {
+        Some(envelope) => envelope,
+        // 原始 __coverage__：插件把 Canyon 字段写进了每个文件的覆盖率对象
+        None => {
+            let build = value
+                .as_object()
+                .and_then(|files| files.values().find_map(|file| build_key_of(file.as_object()?)));
+            (build, None, value)
+        }
+    };
+    Ok(Payload {
+        build: build.unwrap_or_else(|| DEFAULT_BUILD.to_string()),
+        session,
+        coverage: serde_json::from_value(coverage)?,
+    })
+}
+
+/// 单个构建收集到的覆盖率
+#[derive(Clone, Debug, Default)]
+struct BuildCoverage {
+    /// 会话 id -> 该会话最近一次上报，按最近上报的顺序排列
+    sessions: IndexMap<String, CoverageMap>,
+    /// 所有上报合并后的结果
+    merged: CoverageMap,
+}
+
+impl BuildCoverage {
+    /// 减去该会话上一次的数据再合并新数据，开销只和本次上报的大小有关
+    fn replace_session(&mut self, session: &str, map: CoverageMap) {
+        if let Some(previous) = self.sessions.shift_remove(session) {
+            self.merged.subtract(&previous);
+        } else if self.sessions.len() >= MAX_SESSIONS {
+            self.sessions.shift_remove_index(0);
+        }
+        self.merged.merge(&map);
+        self.sessions.insert(session.to_string(), map);
+    }
+}
+
+/// 按构建合并的覆盖率
+#[derive(Clone, Debug, Default)]
+pub struct Collector {
+    builds: IndexMap<String, BuildCoverage>,
+}
+
+impl Collector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 合并一次上报，返回所属构建
+    pub fn add_payload(&mut self, body: &str) -> serde_json::Result<String> {
+        let payload = parse_payload(body)?;
+        match &payload.session {
+            Some(session) => self.replace_session(&payload.build, session, payload.coverage),
+            None => self.add(&payload.build, &payload.coverage),
+        }
+        Ok(payload.build)
+    }
+
+    /// 累加一份覆盖率
+    pub fn add(&mut self, build: &str, map: &CoverageMap) {
+        self.builds.entry(build.to_string()).or_default().merged.merge(map);
+    }
+
+    /// 用会话的最新累计数据替换该会话之前的上报
+    pub fn replace_session(&mut self, build: &str, session: &str, map: CoverageMap) {
+        self.builds.entry(build.to_string()).or_default().replace_session(session, map);
+    }
+
+    pub fn get(&self, build: &str) -> Option<&CoverageMap> {
+        self.builds.get(build).map(|coverage| &coverage.merged)
+    }
+
+    pub fn builds(&self) -> impl Iterator<Item = (&String, &CoverageMap)> {
+        self.builds.iter().map(|(name, coverage)| (name, &coverage.merged))
+    }
+
+    pub fn remove(&mut self, build: &str) -> Option<CoverageMap> {
+        self.builds.shift_remove(build).map(|coverage| coverage.merged)
+    }
+
+    pub fn clear(&mut self) {
+        self.builds.clear();
+    }
+
+    /// 写出 `<dir>/<build>/coverage-final.json` 和 coverage-summary.json，build 为空时写出全部构建
+    pub fn write(&self, dir: &Path, build: Option<&str>) -> io::Result<Vec<PathBuf>> {
+        let mut written = vec![];
+        for (name, map) in self.builds() {
+            if build.is_some_and(|b| b != name) {
+                continue;
+            }
+            let build_dir = dir.join(sanitize(name));
+            std::fs::create_dir_all(&build_dir)?;
+            std::fs::write(build_dir.join(COVERAGE_FINAL_FILE), map.to_json()?)?;
+            write_json_summary(map, &build_dir)?;
+            written.push(build_dir);
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// 一个文件、一个 statement 的覆盖率
+    fn coverage(hits: u32) -> Value {
+        json!({
+            "/app/a.js": {
+                "path": "/app/a.js",
+                "statementMap": { "0": { "start": { "line": 1, "column": 0 }, "end": { "line": 1, "column": 10 } } },
+                "s": { "0": hits },
+            }
+        })
+    }
+
+    fn hits(collector: &Collector, build: &str) -> u32 {
+        collector.get(build).unwrap().get("/app/a.js").unwrap().s["0"]
+    }
+
+    #[test]
+    fn parse_envelope() {
+        let body = json!({ "sha": "abc", "buildID": "42", "sessionId": "s1", "coverage": coverage(1) });
+        let payload = parse_payload(&body.to_string()).unwrap();
+        assert_eq!(payload.build, "42");
+        assert_eq!(payload.session.as_deref(), Some("s1"));
+        assert_eq!(payload.coverage.len(), 1);
+
+        let body = json!({ "sha": "abc", "coverage": coverage(1) });
+        let payload = parse_payload(&body.to_string()).unwrap();
+        assert_eq!(payload.build, "abc");
+        assert_eq!(payload.session, None);
+    }
+
+    #[test]
+    fn parse_raw_coverage() {
+        let mut raw = coverage(1);
+        raw["/app/a.js"]["sha"] = json!("abc");
+        let payload = parse_payload(&raw.to_string()).unwrap();
+        assert_eq!(payload.build, "abc");
+        assert_eq!(payload.session, None);
+
+        let payload = parse_payload(&coverage(1).to_string()).unwrap();
+        assert_eq!(payload.build, DEFAULT_BUILD);
+        assert!(parse_payload("not json").is_err());
+    }
+
+    #[test]
+    fn session_uploads_replace_previous() {
+        let mut collector = Collector::new();
+        for hits in [1, 3, 5] {
+            let body = json!({ "sha": "abc", "sessionId": "s1", "coverage": coverage(hits) });
+            assert_eq!(collector.add_payload(&body.to_string()).unwrap(), "abc");
+        }
+        assert_eq!(hits(&collector, "abc"), 5);
+
+        // 其它会话和没有会话 id 的上报仍然累加
+        let body = json!({ "sha": "abc", "sessionId": "s2", "coverage": coverage(2) });
+        collector.add_payload(&body.to_string()).unwrap();
+        collector.add_payload(&json!({ "sha": "abc", "coverage": coverage(1) }).to_string()).unwrap();
+        collector.add_payload(&json!({ "sha": "abc", "coverage": coverage(1) }).to_string()).unwrap();
+        assert_eq!(hits(&collector, "abc"), 9);
+
+        let body = json!({ "sha": "abc", "sessionId": "s2", "coverage": coverage(4) });
+        collector.add_payload(&body.to_string()).unwrap();
+        assert_eq!(hits(&collector, "abc"), 11);
+    }
+
+    #[test]
+    fn builds_are_separate() {
+        let mut collector = Collector::new();
+        collector.add_payload(&json!({ "sha": "a", "coverage": coverage(1) }).to_string()).unwrap();
+        collector.add_payload(&json!({ "sha": "b", "coverage": coverage(2) }).to_string()).unwrap();
+        let names: Vec<_> = collector.builds().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+
+        assert!(collector.remove("a").is_some());
+        assert!(collector.get("a").is_none());
+        assert_eq!(hits(&collector, "b"), 2);
+        collector.clear();
+        assert_eq!(collector.builds().count(), 0);
+    }
+
+    #[test]
+    fn write_reports() {
+        let dir = std::env::temp_dir().join(format!("swc-coverage-collector-{}", std::process::id()));
+        let mut collector = Collector::new();
+        collector.add("feat/x", &serde_json::from_value(coverage(1)).unwrap());
+        collector.add("other", &serde_json::from_value(coverage(0)).unwrap());
+
+        let written = collector.write(&dir, Some("feat/x")).unwrap();
+        let build_dir = dir.join(sanitize("feat/x"));
+        assert_eq!(written, vec![build_dir.clone()]);
+        let map = CoverageMap::from_json(&std::fs::read_to_string(build_dir.join(COVERAGE_FINAL_FILE)).unwrap())
+            .unwrap();
+        assert_eq!(map.get("/app/a.js").unwrap().s["0"], 1);
+        assert!(build_dir.join("coverage-summary.json").exists());
+        assert!(!dir.join("other").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sanitized_names_do_not_collide() {
+        assert_eq!(sanitize("feat_x"), "feat_x");
+        assert_eq!(sanitize("v1.2-rc"), "v1.2-rc");
+        assert!(sanitize("feat/x").starts_with("feat_x-"));
+        assert_ne!(sanitize("feat/x"), sanitize("feat:x"));
+        assert_ne!(sanitize(".."), "..");
+    }
+
+    #[test]
+    fn oldest_session_is_evicted_but_stays_merged() {
+        let mut collector = Collector::new();
+        for i in 0..=MAX_SESSIONS {
+            collector.replace_session("abc", &format!("s{i}"), serde_json::from_value(coverage(1)).unwrap());
+        }
+        let build = &collector.builds["abc"];
+        assert_eq!(build.sessions.len(), MAX_SESSIONS);
+        assert!(!build.sessions.contains_key("s0"));
+        assert_eq!(hits(&collector, "abc"), MAX_SESSIONS as u32 + 1);
+
+        // 仍在保留中的会话照常替换
+        collector.replace_session("abc", "s1", serde_json::from_value(coverage(3)).unwrap());
+        assert_eq!(hits(&collector, "abc"), MAX_SESSIONS as u32 + 3);
+    }
+}
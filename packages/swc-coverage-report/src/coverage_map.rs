@@ -0,0 +1,360 @@
+//! Istanbul coverage-final.json 的数据模型，学 istanbul-lib-coverage 的 FileCoverage / CoverageMap
+
+use std::collections::{BTreeMap, HashMap};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use swc_coverage_instrument::{BranchMapping, FunctionMapping, Range};
+
+type RangeKey = (u32, u32, u32, u32);
+
+fn range_key(range: &Range) -> RangeKey {
+    (range.start.line, range.start.column, range.end.line, range.end.column)
+}
+
+fn branch_key(mapping: &BranchMapping) -> (RangeKey, Vec<RangeKey>) {
+    (
+        range_key(&mapping.loc),
+        mapping.locations.iter().map(range_key).collect(),
+    )
+}
+
+/// 下一个未被占用的数字 key
+fn next_key<V>(map: &IndexMap<String, V>) -> String {
+    let mut next = map.len();
+    while map.contains_key(&next.to_string()) {
+        next += 1;
+    }
+    next.to_string()
+}
+
+/// 单个文件的覆盖率数据
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileCoverage {
+    pub path: String,
+    #[serde(default)]
+    pub statement_map: IndexMap<String, Range>,
+    #[serde(default)]
+    pub fn_map: IndexMap<String, FunctionMapping>,
+    #[serde(default)]
+    pub branch_map: IndexMap<String, BranchMapping>,
+    #[serde(default)]
+    pub s: IndexMap<String, u32>,
+    #[serde(default)]
+    pub f: IndexMap<String, u32>,
+    #[serde(default)]
+    pub b: IndexMap<String, Vec<u32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_source_map: Option<Value>,
+    /// 其余字段（hash、_coverageSchema、ast 等）原样保留
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+impl FileCoverage {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            ..Default::default()
+        }
+    }
+
+    /// 合并另一份同一文件的覆盖率：位置相同的项累加计数，其余作为新项追加
+    pub fn merge(&mut self, other: &FileCoverage) {
+        self.combine(other, u32::saturating_add);
+    }
+
+    /// merge 的逆操作：从位置相同的项中减去另一份覆盖率的计数，用于替换之前合并过的数据
+    pub fn subtract(&mut self, other: &FileCoverage) {
+        self.combine(other, u32::saturating_sub);
+    }
+
+    fn combine(&mut self, other: &FileCoverage, op: fn(u32, u32) -> u32) {
+        let mut statements: HashMap<RangeKey, String> = self
+            .statement_map
+            .iter()
+            .map(|(k, range)| (range_key(range), k.clone()))
+            .collect();
+        for (k, range) in &other.statement_map {
+            let key = statements
+                .entry(range_key(range))
+                .or_insert_with(|| {
+                    let key = next_key(&self.statement_map);
+                    self.statement_map.insert(key.clone(), *range);
+                    key
+                })
+                .clone();
+            let hits = other.s.get(k).copied().unwrap_or(0);
+            let count = self.s.entry(key).or_insert(0);
+            *count = op(*count, hits);
+        }
+
+        let mut functions: HashMap<(RangeKey, RangeKey), String> = self
+            .fn_map
+            .iter()
+            .map(|(k, m)| ((range_key(&m.decl), range_key(&m.loc)), k.clone()))
+            .collect();
+        for (k, mapping) in &other.fn_map {
+            let key = functions
+                .entry((range_key(&mapping.decl), range_key(&mapping.loc)))
+                .or_insert_with(|| {
+                    let key = next_key(&self.fn_map);
+                    self.fn_map.insert(key.clone(), mapping.clone());
+                    key
+                })
+                .clone();
+            let hits = other.f.get(k).copied().unwrap_or(0);
+            let count = self.f.entry(key).or_insert(0);
+            *count = op(*count, hits);
+        }
+
+        let mut branches: HashMap<(RangeKey, Vec<RangeKey>), String> = self
+            .branch_map
+            .iter()
+            .map(|(k, m)| (branch_key(m), k.clone()))
+            .collect();
+        for (k, mapping) in &other.branch_map {
+            let key = branches
+                .entry(branch_key(mapping))
+                .or_insert_with(|| {
+                    let key = next_key(&self.branch_map);
+                    self.branch_map.insert(key.clone(), mapping.clone());
+                    key
+                })
+                .clone();
+            let hits = other.b.get(k).map(Vec::as_slice).unwrap_or_default();
+            let counts = self.b.entry(key).or_default();
+            if counts.len() < hits.len() {
+                counts.resize(hits.len(), 0);
+            }
+            for (count, hit) in counts.iter_mut().zip(hits) {
+                *count = op(*count, *hit);
+            }
+        }
+    }
+
+    /// 行覆盖率：每个 statement 记在起始行上，同一行取最大计数
+    pub fn line_coverage(&self) -> BTreeMap<u32, u32> {
+        let mut lines = BTreeMap::new();
+        for (k, range) in &self.statement_map {
+            let count = self.s.get(k).copied().unwrap_or(0);
+            let line = lines.entry(range.start.line).or_insert(count);
+            if *line < count {
+                *line = count;
+            }
+        }
+        lines
+    }
+
+    /// 未覆盖的行号（升序）
+    pub fn uncovered_lines(&self) -> Vec<u32> {
+        self.line_coverage()
+            .into_iter()
+            .filter(|(_, count)| *count == 0)
+            .map(|(line, _)| line)
+            .collect()
+    }
+
+    /// 按行统计分支：line -> (covered, total)
+    pub fn branch_coverage_by_line(&self) -> BTreeMap<u32, (u32, u32)> {
+        let mut lines: BTreeMap<u32, (u32, u32)> = BTreeMap::new();
+        for (k, mapping) in &self.branch_map {
+            let line = if mapping.line > 0 {
+                mapping.line
+            } else {
+                mapping.loc.start.line
+            };
+            let hits = self.b.get(k).map(Vec::as_slice).unwrap_or_default();
+            let entry = lines.entry(line).or_default();
+            entry.0 += hits.iter().filter(|h| **h > 0).count() as u32;
+            entry.1 += hits.len() as u32;
+        }
+        lines
+    }
+}
+
+/// 多个文件的覆盖率集合，对应 coverage-final.json
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CoverageMap {
+    files: IndexMap<String, FileCoverage>,
+}
+
+impl CoverageMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// 添加文件覆盖率，已存在时合并
+    pub fn add_file_coverage(&mut self, coverage: FileCoverage) {
+        match self.files.get_mut(&coverage.path) {
+            Some(existing) => existing.merge(&coverage),
+            None => {
+                self.files.insert(coverage.path.clone(), coverage);
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &CoverageMap) {
+        for coverage in other.files.values() {
+            self.add_file_coverage(coverage.clone());
+        }
+    }
+
+    /// 从已有的文件中减去 other 的计数，other 中多出的文件忽略
+    pub fn subtract(&mut self, other: &CoverageMap) {
+        for coverage in other.files.values() {
+            if let Some(existing) = self.files.get_mut(&coverage.path) {
+                existing.subtract(coverage);
+            }
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<&FileCoverage> {
+        self.files.get(path)
+    }
+
+    /// 按路径排序的文件列表
+    pub fn files(&self) -> Vec<&FileCoverage> {
+        let mut files: Vec<_> = self.files.values().collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statement(coverage: &mut FileCoverage, key: &str, line: u32, hits: u32) {
+        coverage.statement_map.insert(key.into(), Range::new(line, 0, line, 10));
+        coverage.s.insert(key.into(), hits);
+    }
+
+    fn function(coverage: &mut FileCoverage, key: &str, line: u32, hits: u32) {
+        let loc = Range::new(line, 0, line + 2, 1);
+        coverage.fn_map.insert(
+            key.into(),
+            FunctionMapping {
+                name: format!("fn{line}"),
+                decl: Range::new(line, 9, line, 12),
+                loc,
+                line,
+            },
+        );
+        coverage.f.insert(key.into(), hits);
+    }
+
+    fn branch(coverage: &mut FileCoverage, key: &str, line: u32, hits: Vec<u32>) {
+        coverage.branch_map.insert(
+            key.into(),
+            BranchMapping {
+                loc: Range::new(line, 0, line, 20),
+                branch_type: "if".into(),
+                locations: (0..hits.len() as u32).map(|i| Range::new(line, i * 10, line, i * 10 + 5)).collect(),
+                line,
+            },
+        );
+        coverage.b.insert(key.into(), hits);
+    }
+
+    #[test]
+    fn merge_by_location() {
+        let mut a = FileCoverage::new("/app/a.js");
+        statement(&mut a, "0", 1, 1);
+        statement(&mut a, "1", 2, 0);
+        function(&mut a, "0", 1, 1);
+        branch(&mut a, "0", 3, vec![1, 0]);
+
+        // 另一次构建中 key 的顺序不同，按位置对应
+        let mut b = FileCoverage::new("/app/a.js");
+        statement(&mut b, "0", 2, 3);
+        statement(&mut b, "1", 1, 2);
+        statement(&mut b, "2", 5, 1);
+        function(&mut b, "0", 1, 4);
+        branch(&mut b, "0", 3, vec![0, 2]);
+
+        a.merge(&b);
+        assert_eq!(a.s.values().copied().collect::<Vec<_>>(), [3, 3, 1]);
+        assert_eq!(a.statement_map["2"].start.line, 5);
+        assert_eq!(a.f["0"], 5);
+        assert_eq!(a.fn_map.len(), 1);
+        assert_eq!(a.b["0"], [1, 2]);
+    }
+
+    #[test]
+    fn subtract_undoes_merge() {
+        let mut a = FileCoverage::new("/app/a.js");
+        statement(&mut a, "0", 1, 1);
+        function(&mut a, "0", 1, 1);
+        branch(&mut a, "0", 3, vec![1, 0]);
+        let mut b = FileCoverage::new("/app/a.js");
+        statement(&mut b, "0", 1, 2);
+        function(&mut b, "0", 1, 3);
+        branch(&mut b, "0", 3, vec![0, 4]);
+
+        let original = a.clone();
+        a.merge(&b);
+        a.subtract(&b);
+        assert_eq!(a, original);
+        a.subtract(&b);
+        assert_eq!((a.s["0"], a.f["0"], a.b["0"].clone()), (0, 0, vec![1, 0]));
+    }
+
+    #[test]
+    fn merge_appends_after_existing_keys() {
+        let mut a = FileCoverage::new("/app/a.js");
+        // key 不连续时新项不能覆盖已有的 "1"
+        statement(&mut a, "1", 1, 1);
+        let mut b = FileCoverage::new("/app/a.js");
+        statement(&mut b, "0", 2, 1);
+        a.merge(&b);
+        assert_eq!(a.statement_map["1"].start.line, 1);
+        assert_eq!(a.statement_map["2"].start.line, 2);
+    }
+
+    #[test]
+    fn line_coverage_takes_max_per_line() {
+        let mut coverage = FileCoverage::new("/app/a.js");
+        statement(&mut coverage, "0", 1, 0);
+        statement(&mut coverage, "1", 1, 2);
+        statement(&mut coverage, "2", 4, 0);
+        branch(&mut coverage, "0", 4, vec![1, 0, 0]);
+        assert_eq!(coverage.line_coverage().into_iter().collect::<Vec<_>>(), [(1, 2), (4, 0)]);
+        assert_eq!(coverage.uncovered_lines(), [4]);
+        assert_eq!(coverage.branch_coverage_by_line().into_iter().collect::<Vec<_>>(), [(4, (1, 3))]);
+    }
+
+    #[test]
+    fn coverage_map_json() {
+        let json = r#"{"/b.js":{"path":"/b.js","statementMap":{"0":{"start":{"line":1,"column":0},"end":{"line":1,"column":3}}},"s":{"0":1},"hash":"x","_coverageSchema":"1a1c"},"/a.js":{"path":"/a.js"}}"#;
+        let mut map = CoverageMap::from_json(json).unwrap();
+        assert_eq!(map.files().iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["/a.js", "/b.js"]);
+        assert_eq!(map.get("/b.js").unwrap().extra["hash"], "x");
+
+        map.merge(&CoverageMap::from_json(json).unwrap());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("/b.js").unwrap().s["0"], 2);
+
+        let round_trip = CoverageMap::from_json(&map.to_json().unwrap()).unwrap();
+        assert_eq!(round_trip, map);
+    }
+}
@@ -0,0 +1,14 @@
+mod collector;
+mod coverage_map;
+mod source_maps;
+mod summary;
+mod server;
+mod thresholds;
+pub mod reporters;
+
+pub use collector::{parse_payload, Collector, Payload, COVERAGE_FINAL_FILE, DEFAULT_BUILD};
+pub use coverage_map::{CoverageMap, FileCoverage};
+pub use source_maps::{load_external_source_map, remap_file_coverage, RemapError};
+pub use server::{serve, ServeOptions};
+pub use summary::{CoverageSummary, Metric, Totals};
+pub use thresholds::{check_coverage, ThresholdConfig, ThresholdError, ThresholdScope, ThresholdViolation, Thresholds};
@@ -0,0 +1,97 @@
+//! json-summary reporter，生成与 istanbul 相同结构的 coverage-summary.json：
+//! ```text
+//! {"total": {"lines":{"total":3,"covered":2,"skipped":0,"pct":66.66},...}
+//! ,"/abs/src/file.js": {"lines":{...},"statements":{...},"functions":{...},"branches":{...}}
+//! }
+//! ```
+
+use std::io;
+use std::path::Path;
+
+use crate::coverage_map::CoverageMap;
+use crate::summary::CoverageSummary;
+
+pub const JSON_SUMMARY_FILE: &str = "coverage-summary.json";
+
+fn write_entry(out: &mut String, key: &str, summary: &CoverageSummary) -> serde_json::Result<()> {
+    out.push(if out.is_empty() { '{' } else { ',' });
+    out.push_str(&serde_json::to_string(key)?);
+    out.push_str(": ");
+    out.push_str(&serde_json::to_string(summary)?);
+    out.push('\n');
+    Ok(())
+}
+
+/// 生成 coverage-summary.json 的内容：先 total，再按路径逐个文件
+pub fn json_summary(map: &CoverageMap) -> serde_json::Result<String> {
+    let mut out = String::new();
+    write_entry(&mut out, "total", &map.summary())?;
+    for (path, summary) in map.summary_by_file() {
+        write_entry(&mut out, &path, &summary)?;
+    }
+    out.push_str("}\n");
+    Ok(out)
+}
+
+/// 写入 `<dir>/coverage-summary.json`
+pub fn write_json_summary(map: &CoverageMap, dir: &Path) -> io::Result<()> {
+    let content = json_summary(map)?;
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join(JSON_SUMMARY_FILE), content)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use swc_coverage_instrument::Range;
+
+    use super::*;
+    use crate::coverage_map::FileCoverage;
+
+    fn map() -> CoverageMap {
+        let mut map = CoverageMap::new();
+        for (path, hits) in [("/app/b.js", [0, 0]), ("/app/a.js", [1, 0])] {
+            let mut coverage = FileCoverage::new(path);
+            for (i, hit) in hits.into_iter().enumerate() {
+                let line = i as u32 + 1;
+                coverage.statement_map.insert(i.to_string(), Range::new(line, 0, line, 10));
+                coverage.s.insert(i.to_string(), hit);
+            }
+            map.add_file_coverage(coverage);
+        }
+        map
+    }
+
+    #[test]
+    fn total_first_then_files_by_path() {
+        let content = json_summary(&map()).unwrap();
+        let lines: Vec<_> = content.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with(r#"{"total": {"lines":{"total":4,"covered":1,"skipped":0,"pct":25}"#));
+        assert!(lines[1].starts_with(r#","/app/a.js": {"#));
+        assert!(lines[2].starts_with(r#","/app/b.js": {"#));
+        assert_eq!(lines[3], "}");
+
+        let summary: Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(summary["total"]["statements"]["pct"], 25);
+        assert_eq!(summary["/app/a.js"]["statements"]["pct"], 50);
+        assert_eq!(summary["/app/b.js"]["branches"]["pct"], 100);
+    }
+
+    #[test]
+    fn empty_map() {
+        let summary: Value = serde_json::from_str(&json_summary(&CoverageMap::new()).unwrap()).unwrap();
+        assert_eq!(summary["total"]["lines"]["total"], 0);
+        assert_eq!(summary["total"]["lines"]["pct"], 100);
+        assert_eq!(summary.as_object().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn write_file() {
+        let dir = std::env::temp_dir().join(format!("swc-coverage-json-summary-{}", std::process::id()));
+        write_json_summary(&map(), &dir).unwrap();
+        let content = std::fs::read_to_string(dir.join(JSON_SUMMARY_FILE)).unwrap();
+        assert_eq!(content, json_summary(&map()).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,58 @@
+pub mod json_summary;
+pub mod text;
+pub mod text_summary;
+
+use serde::{Deserialize, Serialize};
+
+/// 水位线 [low, high]，低于 low 为红色，不低于 high 为绿色，中间为黄色
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Watermarks {
+    pub statements: [f64; 2],
+    pub functions: [f64; 2],
+    pub branches: [f64; 2],
+    pub lines: [f64; 2],
+}
+
+impl Default for Watermarks {
+    fn default() -> Self {
+        Self {
+            statements: [50.0, 80.0],
+            functions: [50.0, 80.0],
+            branches: [50.0, 80.0],
+            lines: [50.0, 80.0],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    Low,
+    Medium,
+    High,
+}
+
+impl Level {
+    pub fn for_pct(pct: f64, watermark: [f64; 2]) -> Self {
+        if pct >= watermark[1] {
+            Level::High
+        } else if pct >= watermark[0] {
+            Level::Medium
+        } else {
+            Level::Low
+        }
+    }
+}
+
+/// 按水位等级给文本上 ANSI 颜色
+pub(crate) fn colorize(text: &str, level: Level, color: bool) -> String {
+    if !color {
+        return text.to_string();
+    }
+    let code = match level {
+        Level::Low => "31;1",
+        Level::Medium => "33;1",
+        Level::High => "32;1",
+    };
+    format!("\u{1b}[{code}m{text}\u{1b}[0m")
+}
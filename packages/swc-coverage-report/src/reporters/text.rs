@@ -0,0 +1,370 @@
+//! text reporter，输出 istanbul 风格的表格，文件分布在多个目录时按目录分组，目录行为该目录下文件的小计：
+//! ```text
+//! -----------|---------|----------|---------|---------|-------------------
+//! File       | % Stmts | % Branch | % Funcs | % Lines | Uncovered Line #s
+//! -----------|---------|----------|---------|---------|-------------------
+//! All files  |     100 |      100 |     100 |     100 |
+//!  src       |     100 |      100 |     100 |     100 |
+//!   index.js |     100 |      100 |     100 |     100 |
+//!  src/util  |     100 |      100 |     100 |     100 |
+//!   math.js  |     100 |      100 |     100 |     100 |
+//! -----------|---------|----------|---------|---------|-------------------
+//! ```
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::{colorize, Level, Watermarks};
+use crate::coverage_map::{CoverageMap, FileCoverage};
+use crate::summary::{parent_dir, CoverageSummary};
+
+const NAME_COL: &str = "File";
+const PCT_COLS: [&str; 4] = ["% Stmts", "% Branch", "% Funcs", "% Lines"];
+const MISSING_COL: &str = "Uncovered Line #s";
+
+#[derive(Clone, Debug)]
+pub struct TextReportOptions {
+    pub watermarks: Watermarks,
+    /// 表格最大宽度，0 表示不限制
+    pub max_cols: usize,
+    /// 跳过没有任何可统计项的文件
+    pub skip_empty: bool,
+    /// 跳过四项均为 100% 的文件
+    pub skip_full: bool,
+    pub color: bool,
+}
+
+impl Default for TextReportOptions {
+    fn default() -> Self {
+        Self {
+            watermarks: Watermarks::default(),
+            max_cols: 80,
+            skip_empty: false,
+            skip_full: false,
+            color: false,
+        }
+    }
+}
+
+struct Row {
+    name: String,
+    summary: CoverageSummary,
+    /// (文本, 是否为未覆盖分支行)
+    missing: Option<(String, bool)>,
+}
+
+/// 把连续的行号合并成区间：[1, 2, 3, 7] -> "1-3,7"
+pub(crate) fn format_line_ranges(lines: &[u32]) -> String {
+    let mut ranges: Vec<String> = vec![];
+    let mut iter = lines.iter().copied().peekable();
+    while let Some(start) = iter.next() {
+        let mut end = start;
+        while iter.peek() == Some(&(end + 1)) {
+            end = iter.next().unwrap_or(end);
+        }
+        if start == end {
+            ranges.push(start.to_string());
+        } else {
+            ranges.push(format!("{start}-{end}"));
+        }
+    }
+    ranges.join(",")
+}
+
+/// 未覆盖行；没有未覆盖行时退而显示分支未全覆盖的行
+fn missing_for(coverage: &FileCoverage) -> Option<(String, bool)> {
+    let lines = coverage.uncovered_lines();
+    if !lines.is_empty() {
+        return Some((format_line_ranges(&lines), false));
+    }
+    let branch_lines: Vec<u32> = coverage
+        .branch_coverage_by_line()
+        .into_iter()
+        .filter(|(_, (covered, total))| covered < total)
+        .map(|(line, _)| line)
+        .collect();
+    if branch_lines.is_empty() {
+        None
+    } else {
+        Some((format_line_ranges(&branch_lines), true))
+    }
+}
+
+/// 所有文件所在目录的公共前缀
+pub(crate) fn common_root(paths: &[&str]) -> String {
+    let mut root: Option<Vec<&str>> = None;
+    for path in paths {
+        let mut parts: Vec<&str> = path.split('/').collect();
+        parts.pop();
+        root = Some(match root {
+            None => parts,
+            Some(prev) => prev
+                .iter()
+                .zip(parts.iter())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| *a)
+                .collect(),
+        });
+    }
+    root.map(|parts| parts.join("/")).unwrap_or_default()
+}
+
+pub(crate) fn relative_path(path: &str, root: &str) -> String {
+    if root.is_empty() {
+        return path.to_string();
+    }
+    path.strip_prefix(root)
+        .map(|rest| rest.trim_start_matches('/').to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn is_full(summary: &CoverageSummary) -> bool {
+    [
+        summary.statements.pct,
+        summary.branches.pct,
+        summary.functions.pct,
+        summary.lines.pct,
+    ]
+    .iter()
+    .all(|pct| *pct == 100.0)
+}
+
+/// 按显示宽度截断，超出时以 `...` 结尾；中文等全角字符占两列
+fn clip(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let limit = width.saturating_sub(3);
+    let mut clipped = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > limit {
+            break;
+        }
+        used += w;
+        clipped.push(c);
+    }
+    clipped.push_str("...");
+    clipped
+}
+
+/// 按显示宽度在右侧补空格，`{:<width$}` 按字符数补齐，全角字符会错位
+fn pad_end(text: &str, width: usize) -> String {
+    format!("{text}{}", " ".repeat(width.saturating_sub(text.width())))
+}
+
+pub fn text_report(map: &CoverageMap, options: &TextReportOptions) -> String {
+    let files = map.files();
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    let root = common_root(&paths);
+
+    // 目录名相对公共根目录的上一级，和 istanbul 一样显示为 `src`、`src/util`
+    let by_dir = map.summary_by_directory();
+    let grouped = by_dir.len() > 1;
+    let base = parent_dir(&root);
+    let mut rows = vec![Row {
+        name: "All files".to_string(),
+        summary: map.summary(),
+        missing: None,
+    }];
+    for (dir, dir_summary) in &by_dir {
+        let indent = if grouped { "  " } else { " " };
+        let file_rows: Vec<Row> = files
+            .iter()
+            .filter(|coverage| parent_dir(&coverage.path) == dir)
+            .map(|coverage| (coverage, CoverageSummary::from_file_coverage(coverage)))
+            .filter(|(_, summary)| {
+                !((options.skip_empty && summary.is_empty()) || (options.skip_full && is_full(summary)))
+            })
+            .map(|(coverage, summary)| Row {
+                name: format!("{indent}{}", relative_path(&coverage.path, dir)),
+                summary,
+                missing: missing_for(coverage),
+            })
+            .collect();
+        if file_rows.is_empty() {
+            continue;
+        }
+        if grouped {
+            let name = match relative_path(dir, base) {
+                name if name.is_empty() => ".".to_string(),
+                name => name,
+            };
+            rows.push(Row {
+                name: format!(" {name}"),
+                summary: *dir_summary,
+                missing: None,
+            });
+        }
+        rows.extend(file_rows);
+    }
+
+    let name_width = rows
+        .iter()
+        .map(|r| r.name.width())
+        .chain(std::iter::once(NAME_COL.len()))
+        .max()
+        .unwrap_or_default();
+    let fixed_width = name_width + PCT_COLS.iter().map(|c| c.len() + 3).sum::<usize>() + 4;
+    let mut missing_width = rows
+        .iter()
+        .filter_map(|r| r.missing.as_ref().map(|(m, _)| m.width()))
+        .chain(std::iter::once(MISSING_COL.len()))
+        .max()
+        .unwrap_or_default();
+    if options.max_cols > 0 {
+        let available = options.max_cols.saturating_sub(fixed_width).max(MISSING_COL.len());
+        missing_width = missing_width.min(available);
+    }
+
+    let separator = {
+        let mut cols = vec!["-".repeat(name_width + 1)];
+        cols.extend(PCT_COLS.iter().map(|c| "-".repeat(c.len() + 2)));
+        cols.push("-".repeat(missing_width + 2));
+        cols.join("|")
+    };
+
+    let mut out = vec![separator.clone()];
+    let mut header = format!("{NAME_COL:<name_width$}");
+    for col in PCT_COLS {
+        header.push_str(&format!(" | {col}"));
+    }
+    header.push_str(&format!(" | {MISSING_COL:<missing_width$} "));
+    out.push(header);
+    out.push(separator.clone());
+
+    let watermarks = &options.watermarks;
+    for row in &rows {
+        let summary = &row.summary;
+        let metrics = [
+            (summary.statements.pct, watermarks.statements),
+            (summary.branches.pct, watermarks.branches),
+            (summary.functions.pct, watermarks.functions),
+            (summary.lines.pct, watermarks.lines),
+        ];
+        let name_level = Level::for_pct(summary.statements.pct, watermarks.statements);
+        let mut line = colorize(&pad_end(&row.name, name_width), name_level, options.color);
+        for ((pct, watermark), col) in metrics.iter().zip(PCT_COLS) {
+            let cell = format!("{:>width$}", pct, width = col.len());
+            line.push_str(" | ");
+            line.push_str(&colorize(&cell, Level::for_pct(*pct, *watermark), options.color));
+        }
+        line.push_str(" | ");
+        match &row.missing {
+            Some((missing, is_branch)) => {
+                let cell = pad_end(&clip(missing, missing_width), missing_width);
+                let level = if *is_branch { Level::Medium } else { Level::Low };
+                line.push_str(&colorize(&cell, level, options.color));
+            }
+            None => line.push_str(&" ".repeat(missing_width)),
+        }
+        line.push(' ');
+        out.push(line);
+    }
+    out.push(separator);
+
+    let mut text = out.join("\n");
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_coverage_instrument::Range;
+
+    use super::*;
+
+    fn file(path: &str, hits: &[u32]) -> FileCoverage {
+        let mut coverage = FileCoverage::new(path);
+        for (i, hit) in hits.iter().enumerate() {
+            let line = i as u32 + 1;
+            coverage.statement_map.insert(i.to_string(), Range::new(line, 0, line, 10));
+            coverage.s.insert(i.to_string(), *hit);
+        }
+        coverage
+    }
+
+    #[test]
+    fn line_ranges_and_paths() {
+        assert_eq!(format_line_ranges(&[1, 2, 3, 7, 9, 10]), "1-3,7,9-10");
+        assert_eq!(format_line_ranges(&[]), "");
+        assert_eq!(common_root(&["/app/src/a.js", "/app/src/util/b.js"]), "/app/src");
+        assert_eq!(common_root(&["/app/src/a.js"]), "/app/src");
+        assert_eq!(relative_path("/app/src/util/b.js", "/app/src"), "util/b.js");
+        assert_eq!(relative_path("/other/b.js", "/app/src"), "/other/b.js");
+    }
+
+    #[test]
+    fn clip_by_display_width() {
+        assert_eq!(clip("1-3,7", 10), "1-3,7");
+        assert_eq!(clip("1-3,7,9-10,12", 10), "1-3,7,9...");
+        // 全角字符占两列，不能从字符中间截断
+        assert_eq!(clip("组件组件组件", 8), "组件...");
+        assert_eq!(clip("组件组件组件", 12), "组件组件组件");
+        assert_eq!(pad_end("组件", 6), "组件  ");
+    }
+
+    #[test]
+    fn table() {
+        let mut map = CoverageMap::new();
+        map.add_file_coverage(file("/app/src/a.js", &[1, 1]));
+        map.add_file_coverage(file("/app/src/组件.js", &[1, 0, 0, 1]));
+        let report = text_report(&map, &TextReportOptions::default());
+        let lines: Vec<_> = report.lines().collect();
+        assert_eq!(lines[1], "File      | % Stmts | % Branch | % Funcs | % Lines | Uncovered Line #s ");
+        assert_eq!(lines[3], "All files |   66.66 |      100 |     100 |   66.66 |                   ");
+        assert_eq!(lines[4], " a.js     |     100 |      100 |     100 |     100 |                   ");
+        assert_eq!(lines[5], " 组件.js  |      50 |      100 |     100 |      50 | 2-3               ");
+        // 每行的显示宽度一致
+        assert!(lines.iter().all(|line| line.width() == lines[0].width()));
+    }
+
+    #[test]
+    fn group_by_directory() {
+        let mut map = CoverageMap::new();
+        map.add_file_coverage(file("/app/src/a.js", &[1, 1]));
+        map.add_file_coverage(file("/app/src/b.js", &[0, 1]));
+        map.add_file_coverage(file("/app/src/util/c.js", &[0, 0, 0, 1]));
+        map.add_file_coverage(file("/app/src/util/deep/d.js", &[1]));
+        let report = text_report(&map, &TextReportOptions::default());
+        let lines: Vec<_> = report.lines().collect();
+        let names: Vec<_> = lines[3..lines.len() - 1]
+            .iter()
+            .map(|line| line.split(" |").next().unwrap().trim_end())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "All files",
+                " src",
+                "  a.js",
+                "  b.js",
+                " src/util",
+                "  c.js",
+                " src/util/deep",
+                "  d.js",
+            ]
+        );
+        // 目录行是该目录下文件的小计，不含子目录
+        assert!(lines[4].starts_with(" src           |      75 |"), "{report}");
+        assert!(lines[7].starts_with(" src/util      |      25 |"), "{report}");
+        assert!(lines[8].starts_with("  c.js         |      25 |      100 |     100 |      25 | 1-3"), "{report}");
+    }
+
+    #[test]
+    fn skip_full_and_empty() {
+        let mut map = CoverageMap::new();
+        map.add_file_coverage(file("/app/a.js", &[1]));
+        map.add_file_coverage(file("/app/b.js", &[0]));
+        map.add_file_coverage(file("/app/empty.js", &[]));
+        let options = TextReportOptions {
+            skip_full: true,
+            skip_empty: true,
+            ..Default::default()
+        };
+        let report = text_report(&map, &options);
+        assert!(report.contains(" b.js"));
+        assert!(!report.contains(" a.js"));
+        assert!(!report.contains("empty.js"));
+    }
+}
@@ -0,0 +1,51 @@
+//! text-summary reporter，输出格式与 istanbul 一致：
+//! ```text
+//! =============================== Coverage summary ===============================
+//! Statements   : 66.66% ( 2/3 )
+//! Branches     : 100% ( 0/0 )
+//! Functions    : 100% ( 1/1 )
+//! Lines        : 66.66% ( 2/3 )
+//! ================================================================================
+//! ```
+
+use super::{colorize, Level, Watermarks};
+use crate::coverage_map::CoverageMap;
+use crate::summary::{CoverageSummary, Totals};
+
+#[derive(Clone, Debug, Default)]
+pub struct TextSummaryOptions {
+    pub watermarks: Watermarks,
+    pub color: bool,
+}
+
+fn line_for(name: &str, totals: &Totals, watermark: [f64; 2], color: bool) -> String {
+    let mut line = format!(
+        "{:<12} : {}% ( {}/{} )",
+        name, totals.pct, totals.covered, totals.total
+    );
+    if totals.skipped > 0 {
+        line.push_str(&format!(", {} ignored", totals.skipped));
+    }
+    colorize(&line, Level::for_pct(totals.pct, watermark), color)
+}
+
+pub fn text_summary(summary: &CoverageSummary, options: &TextSummaryOptions) -> String {
+    let watermarks = &options.watermarks;
+    let lines = [
+        String::new(),
+        "=============================== Coverage summary ===============================".to_string(),
+        line_for("Statements", &summary.statements, watermarks.statements, options.color),
+        line_for("Branches", &summary.branches, watermarks.branches, options.color),
+        line_for("Functions", &summary.functions, watermarks.functions, options.color),
+        line_for("Lines", &summary.lines, watermarks.lines, options.color),
+        "=".repeat(80),
+    ];
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+/// 对整个 CoverageMap 输出 text-summary
+pub fn text_summary_report(map: &CoverageMap, options: &TextSummaryOptions) -> String {
+    text_summary(&map.summary(), options)
+}
@@ -0,0 +1,391 @@
+//! 收集端的 HTTP 服务，只依赖 std，面向本地 / CI 中的 Playwright 等场景：
+//! - `POST /coverage/client`：上报覆盖率（插件 upload 默认的 dsn 路径，其余路径的 POST 同样接受）
+//! - `GET /builds`：各构建已收集的文件数和汇总
+//! - `POST /reports[?build=xxx]`：写出 `<outDir>/<build>/coverage-final.json` 和 coverage-summary.json，返回 text-summary
+//! - `DELETE /coverage[?build=xxx]`：清空已收集的数据
+//!
+//! 浏览器中的 fetch 会带 `Content-Type: application/json` 触发预检，所以所有响应都带 CORS 头
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::collector::Collector;
+use crate::reporters::text_summary::{text_summary_report, TextSummaryOptions};
+
+/// 单次上报的大小上限
+const MAX_BODY: usize = 64 * 1024 * 1024;
+
+/// 请求行和单个请求头的长度上限
+const MAX_LINE: u64 = 8 * 1024;
+
+#[derive(Clone, Debug)]
+pub struct ServeOptions {
+    /// 报告输出目录
+    pub out_dir: PathBuf,
+    /// 每次上报后立即写出该构建的报告
+    pub write_on_upload: bool,
+}
+
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    query: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// 取解码后的查询参数，空值视为没有
+    fn query_param(&self, name: &str) -> Option<String> {
+        self.query
+            .as_deref()?
+            .split('&')
+            .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+            .find(|(key, _)| url_decode(key) == name)
+            .map(|(_, value)| url_decode(value))
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// 解码 `application/x-www-form-urlencoded`：`%XX` 和 `+`，无效的转义原样保留
+fn url_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    body: String,
+    content_type: &'static str,
+}
+
+impl Response {
+    fn json(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            body: body.to_string(),
+            content_type: "application/json",
+        }
+    }
+
+    fn text(status: u16, body: String) -> Self {
+        Self {
+            status,
+            body,
+            content_type: "text/plain; charset=utf-8",
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::json(status, json!({ "error": message.into() }))
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+/// 读取一行（请求行或请求头），超过 MAX_LINE 视为错误请求
+fn read_line(reader: &mut impl BufRead) -> Result<String, Response> {
+    let mut line = String::new();
+    reader
+        .take(MAX_LINE)
+        .read_line(&mut line)
+        .map_err(|e| Response::error(400, e.to_string()))?;
+    if !line.ends_with('\n') && line.len() as u64 == MAX_LINE {
+        return Err(Response::error(400, "header line too long"));
+    }
+    Ok(line)
+}
+
+fn read_request(mut reader: impl BufRead) -> Result<Request, Response> {
+    let line = read_line(&mut reader)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(Response::error(400, "malformed request line"));
+    };
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), Some(query.to_string())),
+        None => (target.to_string(), None),
+    };
+    let method = method.to_ascii_uppercase();
+
+    let mut content_length = None;
+    loop {
+        let header = read_line(&mut reader)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                let length = value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| Response::error(400, "invalid content-length"))?;
+                content_length = Some(length);
+            }
+        }
+    }
+
+    // 不支持 chunked，没有 Content-Length 时按空 body 处理
+    let length = content_length.unwrap_or(0);
+    if length > MAX_BODY {
+        return Err(Response::error(413, "payload too large"));
+    }
+    // 按实际收到的数据增长缓冲区，不按声明的长度一次分配
+    let mut body = Vec::with_capacity(length.min(64 * 1024));
+    reader
+        .take(length as u64)
+        .read_to_end(&mut body)
+        .map_err(|e| Response::error(400, e.to_string()))?;
+    if body.len() < length {
+        return Err(Response::error(400, "unexpected end of request body"));
+    }
+    Ok(Request {
+        method,
+        path,
+        query,
+        body,
+    })
+}
+
+fn write_response(mut stream: &TcpStream, response: &Response) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: {}\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Methods: GET, POST, DELETE, OPTIONS\r\n\
+         Access-Control-Allow-Headers: Content-Type\r\n\
+         Connection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(response.body.as_bytes())?;
+    stream.flush()
+}
+
+fn write_reports(collector: &Collector, options: &ServeOptions, build: Option<&str>) -> Response {
+    let written = match collector.write(&options.out_dir, build) {
+        Ok(written) => written,
+        Err(e) => return Response::error(500, e.to_string()),
+    };
+    if written.is_empty() {
+        return Response::error(404, "no coverage collected");
+    }
+    let mut body = String::new();
+    for (name, map) in collector.builds() {
+        if build.is_some_and(|b| b != name) {
+            continue;
+        }
+        body.push_str(&format!("build: {name}\n"));
+        body.push_str(&text_summary_report(map, &TextSummaryOptions::default()));
+    }
+    for dir in written {
+        println!("wrote {}", dir.display());
+    }
+    Response::text(200, body)
+}
+
+fn handle(collector: &mut Collector, options: &ServeOptions, request: &Request) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("OPTIONS", _) => Response::text(204, String::new()),
+        ("GET", "/builds") => {
+            let builds: serde_json::Map<_, _> = collector
+                .builds()
+                .map(|(name, map)| (name.clone(), json!({ "files": map.len(), "total": map.summary() })))
+                .collect();
+            Response::json(200, builds.into())
+        }
+        ("POST", "/reports") => write_reports(collector, options, request.query_param("build").as_deref()),
+        ("DELETE", "/coverage") => {
+            match request.query_param("build") {
+                Some(build) => {
+                    collector.remove(&build);
+                }
+                None => collector.clear(),
+            }
+            Response::json(200, json!({ "ok": true }))
+        }
+        ("POST", _) => {
+            let body = String::from_utf8_lossy(&request.body);
+            match collector.add_payload(&body) {
+                Ok(build) => {
+                    let files = collector.get(&build).map(|map| map.len()).unwrap_or(0);
+                    println!("received coverage for build {build} ({files} files)");
+                    if options.write_on_upload {
+                        if let Err(e) = collector.write(&options.out_dir, Some(&build)) {
+                            return Response::error(500, e.to_string());
+                        }
+                    }
+                    Response::json(200, json!({ "build": build, "files": files }))
+                }
+                Err(e) => Response::error(400, format!("invalid coverage payload: {e}")),
+            }
+        }
+        _ => Response::error(404, "not found"),
+    }
+}
+
+/// 逐个处理连接，直到监听出错
+pub fn serve(listener: TcpListener, collector: &mut Collector, options: &ServeOptions) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        // 连接是串行处理的，设置读超时避免卡住的客户端阻塞后续上报
+        stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+        let response = match read_request(BufReader::new(&stream)) {
+            Ok(request) => handle(collector, options, &request),
+            Err(response) => response,
+        };
+        if let Err(e) = write_response(&stream, &response) {
+            eprintln!("failed to write response: {e}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: &str) -> Result<Request, Response> {
+        read_request(raw.as_bytes())
+    }
+
+    fn post(body: &str) -> String {
+        format!("POST /coverage/client HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}", body.len())
+    }
+
+    fn options() -> ServeOptions {
+        ServeOptions {
+            out_dir: std::env::temp_dir().join("swc-coverage-server-test"),
+            write_on_upload: false,
+        }
+    }
+
+    fn coverage(hits: u32) -> serde_json::Value {
+        json!({
+            "/app/a.js": {
+                "path": "/app/a.js",
+                "statementMap": { "0": { "start": { "line": 1, "column": 0 }, "end": { "line": 1, "column": 10 } } },
+                "s": { "0": hits },
+            }
+        })
+    }
+
+    #[test]
+    fn parse_request() {
+        let request = parse(&post(r#"{"a":1}"#)).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/coverage/client");
+        assert_eq!(request.query, None);
+        assert_eq!(request.body, br#"{"a":1}"#);
+
+        let request = parse("get /builds?x=1 HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/builds");
+        assert_eq!(request.query.as_deref(), Some("x=1"));
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn reject_bad_requests() {
+        assert_eq!(parse("\r\n").err().unwrap().status, 400);
+        assert_eq!(parse("POST / HTTP/1.1\r\nContent-Length: abc\r\n\r\n").err().unwrap().status, 400);
+        let too_large = format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY + 1);
+        assert_eq!(parse(&too_large).err().unwrap().status, 413);
+        // body 比 Content-Length 短
+        assert_eq!(parse("POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}").err().unwrap().status, 400);
+        let long_header = format!("GET / HTTP/1.1\r\nX-Long: {}\r\n\r\n", "a".repeat(MAX_LINE as usize));
+        assert_eq!(parse(&long_header).err().unwrap().status, 400);
+    }
+
+    #[test]
+    fn decode_query_params() {
+        let request = parse("POST /reports?build=feat%2Fx+1&empty=&flag HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.query_param("build").as_deref(), Some("feat/x 1"));
+        assert_eq!(request.query_param("empty"), None);
+        assert_eq!(request.query_param("flag"), None);
+        assert_eq!(request.query_param("missing"), None);
+        assert_eq!(url_decode("100%"), "100%");
+        assert_eq!(url_decode("%e4%b8%ad%zz"), "中%zz");
+    }
+
+    #[test]
+    fn handle_uploads() {
+        let mut collector = Collector::new();
+        let options = options();
+        for hits in [1, 2] {
+            let body = json!({ "sha": "abc", "sessionId": "s1", "coverage": coverage(hits) }).to_string();
+            let response = handle(&mut collector, &options, &parse(&post(&body)).unwrap());
+            assert_eq!(response.status, 200);
+            assert_eq!(response.body, json!({ "build": "abc", "files": 1 }).to_string());
+        }
+        let body = json!({ "sha": "abc", "sessionId": "s2", "coverage": coverage(3) }).to_string();
+        handle(&mut collector, &options, &parse(&post(&body)).unwrap());
+        assert_eq!(collector.get("abc").unwrap().get("/app/a.js").unwrap().s["0"], 5);
+
+        let response = handle(&mut collector, &options, &parse("GET /builds HTTP/1.1\r\n\r\n").unwrap());
+        assert_eq!(response.status, 200);
+        let builds: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(builds["abc"]["files"], 1);
+        assert_eq!(builds["abc"]["total"]["statements"]["covered"], 1);
+
+        let response = handle(&mut collector, &options, &parse(&post("not json")).unwrap());
+        assert_eq!(response.status, 400);
+    }
+
+    #[test]
+    fn handle_delete_and_options() {
+        let mut collector = Collector::new();
+        let options = options();
+        for build in ["feat/x", "main"] {
+            let body = json!({ "sha": build, "coverage": coverage(1) }).to_string();
+            handle(&mut collector, &options, &parse(&post(&body)).unwrap());
+        }
+        let request = parse("DELETE /coverage?build=feat%2Fx HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(handle(&mut collector, &options, &request).status, 200);
+        assert!(collector.get("feat/x").is_none());
+        assert!(collector.get("main").is_some());
+
+        let request = parse("OPTIONS /coverage/client HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(handle(&mut collector, &options, &request).status, 204);
+        let request = parse("GET /nope HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(handle(&mut collector, &options, &request).status, 404);
+    }
+}
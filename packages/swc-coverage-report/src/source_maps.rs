@@ -0,0 +1,462 @@
+//! 按 source map 把生成代码上的覆盖率映射回原始文件，学 istanbul-lib-source-maps：
+//! - statement / function / branch 的 range 逐个映射，起止落在不同源文件或无法映射的直接丢弃
+//! - 一个生成文件可以拆成多个原始文件的 FileCoverage
+//! - 多个生成位置映射到同一原始位置时合并计数（见 FileCoverage::merge）
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use swc_coverage_instrument::{BranchMapping, FunctionMapping, Location, Range};
+use swc_sourcemap::SourceMap;
+
+use crate::coverage_map::{CoverageMap, FileCoverage};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RemapError {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for RemapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to read source map for {}: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for RemapError {}
+
+/// 映射后的位置
+struct Mapped {
+    src_id: u32,
+    range: Range,
+}
+
+/// 按生成行索引 token，用于在同一行内查找最近的映射
+struct LineIndex<'a> {
+    source_map: &'a SourceMap,
+    lines: BTreeMap<u32, Vec<(u32, usize)>>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(source_map: &'a SourceMap) -> Self {
+        let mut lines: BTreeMap<u32, Vec<(u32, usize)>> = BTreeMap::new();
+        for (idx, token) in source_map.tokens().enumerate() {
+            if token.has_source() && token.get_src_line() != u32::MAX {
+                lines
+                    .entry(token.get_dst_line())
+                    .or_default()
+                    .push((token.get_dst_col(), idx));
+            }
+        }
+        for tokens in lines.values_mut() {
+            tokens.sort();
+        }
+        Self { source_map, lines }
+    }
+
+    /// 同一行内先取不大于 col 的最近映射，没有时取之后的第一个；返回 (src_id, src_line, src_col, dst_col)
+    fn lookup(&self, line: u32, col: u32) -> Option<(u32, u32, u32, u32)> {
+        let tokens = self.lines.get(&line)?;
+        let pos = tokens.partition_point(|(dst_col, _)| *dst_col <= col);
+        let (_, idx) = if pos > 0 { tokens[pos - 1] } else { *tokens.first()? };
+        let token = self.source_map.get_token(idx)?;
+        Some((
+            token.get_src_id(),
+            token.get_src_line(),
+            token.get_src_col(),
+            token.get_dst_col(),
+        ))
+    }
+
+    /// 把生成代码中的 range（行从 1 开始）映射回原始文件
+    fn map_range(&self, range: &Range) -> Option<Mapped> {
+        let start_line = range.start.line.checked_sub(1)?;
+        let end_line = range.end.line.checked_sub(1)?;
+        let (src_id, line, column, _) = self.lookup(start_line, range.start.column)?;
+
+        let end_col = range.end.column.saturating_sub(1);
+        let (end_src_id, end_line, end_src_col, end_dst_col) = self.lookup(end_line, end_col)?;
+        if end_src_id != src_id {
+            return None;
+        }
+        let end_column = if range.end.column == u32::MAX {
+            u32::MAX
+        } else {
+            end_src_col + end_col.saturating_sub(end_dst_col) + 1
+        };
+        let start = Location { line: line + 1, column };
+        let end = Location {
+            line: end_line + 1,
+            column: end_column,
+        };
+        if (end.line, end.column) < (start.line, start.column) {
+            return None;
+        }
+        Some(Mapped {
+            src_id,
+            range: Range {
+                start,
+                end,
+                skip: range.skip,
+            },
+        })
+    }
+}
+
+/// 按生成顺序追加映射结果，重复位置留给 FileCoverage::merge 合并
+fn push_statement(file: &mut FileCoverage, range: Range, hits: u32) {
+    let key = file.statement_map.len().to_string();
+    file.statement_map.insert(key.clone(), range);
+    file.s.insert(key, hits);
+}
+
+fn push_function(file: &mut FileCoverage, name: &str, decl: Range, loc: Range, hits: u32) {
+    let key = file.fn_map.len().to_string();
+    file.fn_map.insert(
+        key.clone(),
+        FunctionMapping {
+            name: name.to_string(),
+            decl,
+            loc,
+            line: loc.start.line,
+        },
+    );
+    file.f.insert(key, hits);
+}
+
+fn push_branch(file: &mut FileCoverage, branch_type: &str, loc: Range, locations: Vec<Range>, hits: Vec<u32>) {
+    let key = file.branch_map.len().to_string();
+    file.branch_map.insert(
+        key.clone(),
+        BranchMapping {
+            loc,
+            branch_type: branch_type.to_string(),
+            locations,
+            line: loc.start.line,
+        },
+    );
+    file.b.insert(key, hits);
+}
+
+/// 规范化 `a/./b/../c` 这类路径
+fn normalize_path(path: &str) -> String {
+    let absolute = path.starts_with('/');
+    let mut parts: Vec<&str> = vec![];
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.last().is_some_and(|p| *p != "..") {
+                    parts.pop();
+                } else if !absolute {
+                    parts.push("..");
+                }
+            }
+            _ => parts.push(part),
+        }
+    }
+    let joined = parts.join("/");
+    if absolute {
+        format!("/{joined}")
+    } else {
+        joined
+    }
+}
+
+/// 源文件路径：绝对路径直接使用，相对路径相对于生成文件所在目录
+fn resolve_source(source: &str, generated_path: &str) -> String {
+    let source = source.strip_prefix("file://").unwrap_or(source);
+    let is_absolute = source.starts_with('/')
+        || source.contains("://")
+        || source.as_bytes().get(1) == Some(&b':');
+    if is_absolute {
+        return source.to_string();
+    }
+    let dir = match generated_path.rfind('/') {
+        Some(idx) => &generated_path[..idx],
+        None => "",
+    };
+    if dir.is_empty() {
+        normalize_path(source)
+    } else {
+        normalize_path(&format!("{dir}/{source}"))
+    }
+}
+
+/// 用给定的 source map 映射单个文件的覆盖率，返回按原始文件拆分的结果
+pub fn remap_file_coverage(coverage: &FileCoverage, source_map: &SourceMap) -> CoverageMap {
+    let index = LineIndex::new(source_map);
+    let mut mapped: BTreeMap<u32, FileCoverage> = BTreeMap::new();
+
+    for (k, range) in &coverage.statement_map {
+        if let Some(m) = index.map_range(range) {
+            let hits = coverage.s.get(k).copied().unwrap_or(0);
+            push_statement(mapped.entry(m.src_id).or_default(), m.range, hits);
+        }
+    }
+
+    for (k, mapping) in &coverage.fn_map {
+        let (Some(decl), Some(loc)) = (index.map_range(&mapping.decl), index.map_range(&mapping.loc)) else {
+            continue;
+        };
+        if decl.src_id != loc.src_id {
+            continue;
+        }
+        let hits = coverage.f.get(k).copied().unwrap_or(0);
+        push_function(
+            mapped.entry(loc.src_id).or_default(),
+            &mapping.name,
+            decl.range,
+            loc.range,
+            hits,
+        );
+    }
+
+    for (k, mapping) in &coverage.branch_map {
+        let hits = coverage.b.get(k).cloned().unwrap_or_default();
+        let mut src_id = None;
+        let mut locations = vec![];
+        let mut mapped_hits = vec![];
+        let mut mixed_sources = false;
+        for (i, location) in mapping.locations.iter().enumerate() {
+            let Some(m) = index.map_range(location) else {
+                continue;
+            };
+            if *src_id.get_or_insert(m.src_id) != m.src_id {
+                mixed_sources = true;
+            }
+            locations.push(m.range);
+            mapped_hits.push(hits.get(i).copied().unwrap_or(0));
+        }
+        let Some(src_id) = src_id else {
+            continue;
+        };
+        if mixed_sources {
+            continue;
+        }
+        let loc = index
+            .map_range(&mapping.loc)
+            .filter(|m| m.src_id == src_id)
+            .map(|m| m.range)
+            .unwrap_or(locations[0]);
+        push_branch(
+            mapped.entry(src_id).or_default(),
+            &mapping.branch_type,
+            loc,
+            locations,
+            mapped_hits,
+        );
+    }
+
+    let mut result = CoverageMap::new();
+    for (src_id, raw) in mapped {
+        let Some(source) = source_map.get_source(src_id) else {
+            continue;
+        };
+        let mut file = FileCoverage::new(resolve_source(source, &coverage.path));
+        file.merge(&raw);
+        result.add_file_coverage(file);
+    }
+    result
+}
+
+/// 读取生成文件旁的外部 source map：优先 sourceMappingURL 注释，其次 `<file>.map`
+pub fn load_external_source_map(generated_path: &str) -> Option<SourceMap> {
+    let path = Path::new(generated_path);
+    let mut candidates = vec![];
+    if let Ok(code) = std::fs::read_to_string(path) {
+        let url = code
+            .lines()
+            .rev()
+            .find_map(|line| line.trim().strip_prefix("//# sourceMappingURL="));
+        if let Some(url) = url.filter(|u| !u.starts_with("data:")) {
+            candidates.push(path.with_file_name(url.trim()));
+        }
+    }
+    candidates.push(path.with_file_name(format!(
+        "{}.map",
+        path.file_name()?.to_string_lossy()
+    )));
+    candidates
+        .into_iter()
+        .find_map(|candidate| std::fs::read(candidate).ok())
+        .and_then(|bytes| SourceMap::from_slice(&bytes).ok())
+}
+
+impl CoverageMap {
+    /// 对每个文件使用 inputSourceMap（没有时尝试外部 .map 文件）映射回原始文件，
+    /// 没有 source map 的文件原样保留
+    pub fn remap_source_maps(&self) -> Result<CoverageMap, RemapError> {
+        let mut result = CoverageMap::new();
+        for coverage in self.files() {
+            let source_map = match &coverage.input_source_map {
+                Some(value) => {
+                    let bytes = serde_json::to_vec(value).map_err(|e| RemapError {
+                        path: coverage.path.clone(),
+                        message: e.to_string(),
+                    })?;
+                    Some(SourceMap::from_slice(&bytes).map_err(|e| RemapError {
+                        path: coverage.path.clone(),
+                        message: e.to_string(),
+                    })?)
+                }
+                None => load_external_source_map(&coverage.path),
+            };
+            match source_map {
+                Some(source_map) => result.merge(&remap_file_coverage(coverage, &source_map)),
+                None => result.add_file_coverage(coverage.clone()),
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// 生成文件 /app/dist/out.js：
+    /// - 第 1 行：列 0 -> a.ts 3:4，列 10 -> a.ts 3:14
+    /// - 第 2 行：列 0 -> b.ts 1:0，列 8 -> b.ts 1:8
+    /// - 第 3 行：列 0 -> a.ts 6:2
+    fn source_map_json() -> serde_json::Value {
+        json!({
+            "version": 3,
+            "sources": ["../src/a.ts", "b.ts"],
+            "names": [],
+            "mappings": "AAEI,UAAU;ACFd,QAAQ;ADKN",
+        })
+    }
+
+    fn source_map() -> SourceMap {
+        SourceMap::from_slice(source_map_json().to_string().as_bytes()).unwrap()
+    }
+
+    fn mapped_range(index: &LineIndex, range: Range) -> Option<(u32, Range)> {
+        index.map_range(&range).map(|m| (m.src_id, m.range))
+    }
+
+    #[test]
+    fn map_range() {
+        let source_map = source_map();
+        let index = LineIndex::new(&source_map);
+        assert_eq!(
+            mapped_range(&index, Range::new(1, 0, 1, 20)),
+            Some((0, Range::new(3, 4, 3, 24)))
+        );
+        // 起始列在第一个映射之前时取同一行的第一个映射
+        assert_eq!(
+            mapped_range(&index, Range::new(2, 0, 2, 8)),
+            Some((1, Range::new(1, 0, 1, 8)))
+        );
+        let mut to_line_end = Range::new(3, 0, 3, u32::MAX);
+        to_line_end.skip = true;
+        let mut expected = Range::new(6, 2, 6, u32::MAX);
+        expected.skip = true;
+        assert_eq!(mapped_range(&index, to_line_end), Some((0, expected)));
+
+        // 起止落在不同源文件、没有映射的行
+        assert_eq!(mapped_range(&index, Range::new(1, 0, 2, 5)), None);
+        assert_eq!(mapped_range(&index, Range::new(4, 0, 4, 5)), None);
+        assert_eq!(mapped_range(&index, Range::new(0, 0, 1, 5)), None);
+    }
+
+    #[test]
+    fn remap_and_merge() {
+        let mut coverage = FileCoverage::new("/app/dist/out.js");
+        // 两个生成位置映射到同一原始位置，计数合并
+        for (key, range, hits) in [
+            ("0", Range::new(1, 0, 1, 20), 1),
+            ("1", Range::new(1, 0, 1, 20), 2),
+            ("2", Range::new(2, 0, 2, 8), 0),
+            ("3", Range::new(1, 0, 2, 5), 7),
+        ] {
+            coverage.statement_map.insert(key.into(), range);
+            coverage.s.insert(key.into(), hits);
+        }
+        coverage.fn_map.insert(
+            "0".into(),
+            FunctionMapping {
+                name: "run".into(),
+                decl: Range::new(1, 10, 1, 13),
+                loc: Range::new(1, 0, 3, 5),
+                line: 1,
+            },
+        );
+        coverage.f.insert("0".into(), 4);
+        for (key, locations) in [
+            ("0", vec![Range::new(1, 0, 1, 5), Range::new(3, 0, 3, 5)]),
+            // 分支路径落在不同源文件，整个分支丢弃
+            ("1", vec![Range::new(1, 0, 1, 5), Range::new(2, 0, 2, 5)]),
+        ] {
+            coverage.branch_map.insert(
+                key.into(),
+                BranchMapping {
+                    loc: Range::new(1, 0, 3, 5),
+                    branch_type: "if".into(),
+                    locations,
+                    line: 1,
+                },
+            );
+            coverage.b.insert(key.into(), vec![1, 0]);
+        }
+
+        let remapped = remap_file_coverage(&coverage, &source_map());
+        let paths: Vec<_> = remapped.files().iter().map(|f| f.path.clone()).collect();
+        assert_eq!(paths, ["/app/dist/b.ts", "/app/src/a.ts"]);
+
+        let a = remapped.get("/app/src/a.ts").unwrap();
+        assert_eq!(a.statement_map.len(), 1);
+        assert_eq!(a.statement_map["0"], Range::new(3, 4, 3, 24));
+        assert_eq!(a.s["0"], 3);
+        assert_eq!(a.fn_map["0"].name, "run");
+        assert_eq!(a.fn_map["0"].decl.start, Location { line: 3, column: 14 });
+        assert_eq!(a.f["0"], 4);
+        assert_eq!(a.branch_map.len(), 1);
+        assert_eq!(a.branch_map["0"].locations[1].start, Location { line: 6, column: 2 });
+        assert_eq!(a.b["0"], [1, 0]);
+
+        let b = remapped.get("/app/dist/b.ts").unwrap();
+        assert_eq!(b.s.values().copied().collect::<Vec<_>>(), [0]);
+        assert!(b.fn_map.is_empty() && b.branch_map.is_empty());
+    }
+
+    #[test]
+    fn remap_coverage_map() {
+        let mut with_map = FileCoverage::new("/app/dist/out.js");
+        with_map.statement_map.insert("0".into(), Range::new(2, 0, 2, 8));
+        with_map.s.insert("0".into(), 1);
+        with_map.input_source_map = Some(source_map_json());
+        let mut plain = FileCoverage::new("/nonexistent/plain.js");
+        plain.statement_map.insert("0".into(), Range::new(1, 0, 1, 8));
+        plain.s.insert("0".into(), 1);
+
+        let mut map = CoverageMap::new();
+        map.add_file_coverage(with_map.clone());
+        map.add_file_coverage(plain.clone());
+        let remapped = map.remap_source_maps().unwrap();
+        assert_eq!(remapped.len(), 2);
+        assert_eq!(remapped.get("/app/dist/b.ts").unwrap().s["0"], 1);
+        assert_eq!(remapped.get("/nonexistent/plain.js"), Some(&plain));
+
+        with_map.input_source_map = Some(json!({ "version": 3, "mappings": 42 }));
+        let mut map = CoverageMap::new();
+        map.add_file_coverage(with_map);
+        let error = map.remap_source_maps().unwrap_err();
+        assert_eq!(error.path, "/app/dist/out.js");
+    }
+
+    #[test]
+    fn resolve_source_paths() {
+        assert_eq!(normalize_path("/a/./b/../c"), "/a/c");
+        assert_eq!(normalize_path("../a/../../b"), "../../b");
+        assert_eq!(resolve_source("../src/a.ts", "/app/dist/out.js"), "/app/src/a.ts");
+        assert_eq!(resolve_source("file:///abs/a.ts", "/app/dist/out.js"), "/abs/a.ts");
+        assert_eq!(resolve_source("webpack://app/a.ts", "/app/dist/out.js"), "webpack://app/a.ts");
+        assert_eq!(resolve_source("C:/src/a.ts", "/app/dist/out.js"), "C:/src/a.ts");
+        assert_eq!(resolve_source("./a.ts", "out.js"), "a.ts");
+    }
+}
@@ -0,0 +1,308 @@
+//! 覆盖率汇总，学 istanbul-lib-coverage 的 CoverageSummary
+
+use std::collections::BTreeMap;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::coverage_map::{CoverageMap, FileCoverage};
+
+/// 单项指标 { total, covered, skipped, pct }，skipped 的项不计入 total 和 covered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Totals {
+    pub total: u32,
+    pub covered: u32,
+    #[serde(default)]
+    pub skipped: u32,
+    #[serde(serialize_with = "serialize_pct")]
+    pub pct: f64,
+}
+
+impl Totals {
+    pub fn new(total: u32, covered: u32, skipped: u32) -> Self {
+        Self {
+            total,
+            covered,
+            skipped,
+            pct: percent(covered, total),
+        }
+    }
+
+    pub fn merge(&mut self, other: &Totals) {
+        *self = Totals::new(
+            self.total + other.total,
+            self.covered + other.covered,
+            self.skipped + other.skipped,
+        );
+    }
+}
+
+/// 整数百分比按 JS 的方式输出为 100 而不是 100.0
+fn serialize_pct<S: Serializer>(pct: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    if pct.fract() == 0.0 {
+        serializer.serialize_u64(*pct as u64)
+    } else {
+        serializer.serialize_f64(*pct)
+    }
+}
+
+/// 与 istanbul 一致：保留两位小数（截断），total 为 0 时视为 100%
+fn percent(covered: u32, total: u32) -> f64 {
+    if total == 0 {
+        return 100.0;
+    }
+    (covered as f64 * 10000.0 / total as f64).floor() / 100.0
+}
+
+/// 四项指标
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    Lines,
+    Statements,
+    Functions,
+    Branches,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 4] = [
+        Metric::Lines,
+        Metric::Statements,
+        Metric::Functions,
+        Metric::Branches,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Lines => "lines",
+            Metric::Statements => "statements",
+            Metric::Functions => "functions",
+            Metric::Branches => "branches",
+        }
+    }
+}
+
+/// 四项指标汇总
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CoverageSummary {
+    pub lines: Totals,
+    pub statements: Totals,
+    pub functions: Totals,
+    pub branches: Totals,
+}
+
+impl Default for CoverageSummary {
+    fn default() -> Self {
+        let empty = Totals::new(0, 0, 0);
+        Self {
+            lines: empty,
+            statements: empty,
+            functions: empty,
+            branches: empty,
+        }
+    }
+}
+
+impl CoverageSummary {
+    pub fn from_file_coverage(coverage: &FileCoverage) -> Self {
+        let lines = coverage.line_coverage();
+        let (mut branch_total, mut branch_covered) = (0, 0);
+        for hits in coverage.b.values() {
+            branch_total += hits.len() as u32;
+            branch_covered += hits.iter().filter(|h| **h > 0).count() as u32;
+        }
+
+        Self {
+            lines: Totals::new(
+                lines.len() as u32,
+                lines.values().filter(|c| **c > 0).count() as u32,
+                0,
+            ),
+            statements: simple_totals(&coverage.s, |k| {
+                coverage.statement_map.get(k).is_some_and(|r| r.skip)
+            }),
+            functions: simple_totals(&coverage.f, |k| {
+                coverage.fn_map.get(k).is_some_and(|m| m.loc.skip)
+            }),
+            branches: Totals::new(branch_total, branch_covered, 0),
+        }
+    }
+
+    pub fn get(&self, metric: Metric) -> &Totals {
+        match metric {
+            Metric::Lines => &self.lines,
+            Metric::Statements => &self.statements,
+            Metric::Functions => &self.functions,
+            Metric::Branches => &self.branches,
+        }
+    }
+
+    pub fn merge(&mut self, other: &CoverageSummary) {
+        self.lines.merge(&other.lines);
+        self.statements.merge(&other.statements);
+        self.functions.merge(&other.functions);
+        self.branches.merge(&other.branches);
+    }
+
+    /// 是否全部为空（没有任何可统计的项）
+    pub fn is_empty(&self) -> bool {
+        self.lines.total == 0
+            && self.statements.total == 0
+            && self.functions.total == 0
+            && self.branches.total == 0
+    }
+}
+
+fn simple_totals(hits: &IndexMap<String, u32>, is_skipped: impl Fn(&str) -> bool) -> Totals {
+    let (mut total, mut covered, mut skipped) = (0, 0, 0);
+    for (k, count) in hits {
+        if is_skipped(k) {
+            skipped += 1;
+            continue;
+        }
+        total += 1;
+        if *count > 0 {
+            covered += 1;
+        }
+    }
+    Totals::new(total, covered, skipped)
+}
+
+impl CoverageMap {
+    /// 所有文件的总汇总
+    pub fn summary(&self) -> CoverageSummary {
+        let mut summary = CoverageSummary::default();
+        for coverage in self.files() {
+            summary.merge(&CoverageSummary::from_file_coverage(coverage));
+        }
+        summary
+    }
+
+    /// 每个文件的汇总，key 为文件路径
+    pub fn summary_by_file(&self) -> BTreeMap<String, CoverageSummary> {
+        self.files()
+            .into_iter()
+            .map(|coverage| (coverage.path.clone(), CoverageSummary::from_file_coverage(coverage)))
+            .collect()
+    }
+
+    /// 按文件所在目录聚合的汇总，key 为目录路径
+    pub fn summary_by_directory(&self) -> BTreeMap<String, CoverageSummary> {
+        let mut dirs: BTreeMap<String, CoverageSummary> = BTreeMap::new();
+        for coverage in self.files() {
+            dirs.entry(parent_dir(&coverage.path).to_string())
+                .or_default()
+                .merge(&CoverageSummary::from_file_coverage(coverage));
+        }
+        dirs
+    }
+}
+
+/// 文件所在目录，没有 `/` 时为空字符串
+pub(crate) fn parent_dir(path: &str) -> &str {
+    path.rfind('/').map(|idx| &path[..idx]).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_coverage_instrument::Range;
+
+    use super::*;
+
+    #[test]
+    fn percentages() {
+        assert_eq!(Totals::new(3, 2, 0).pct, 66.66);
+        assert_eq!(Totals::new(0, 0, 0).pct, 100.0);
+        assert_eq!(Totals::new(7, 0, 0).pct, 0.0);
+        assert_eq!(serde_json::to_string(&Totals::new(2, 2, 0)).unwrap(), r#"{"total":2,"covered":2,"skipped":0,"pct":100}"#);
+        assert_eq!(serde_json::to_string(&Totals::new(3, 1, 0)).unwrap(), r#"{"total":3,"covered":1,"skipped":0,"pct":33.33}"#);
+
+        let mut totals = Totals::new(3, 1, 0);
+        totals.merge(&Totals::new(1, 1, 1));
+        assert_eq!(totals, Totals::new(4, 2, 1));
+        assert_eq!(totals.pct, 50.0);
+    }
+
+    #[test]
+    fn file_summary() {
+        let mut coverage = FileCoverage::new("/app/a.js");
+        for (i, (line, hits)) in [(1, 1), (1, 0), (2, 0), (3, 0)].into_iter().enumerate() {
+            coverage.statement_map.insert(i.to_string(), Range::new(line, i as u32, line, 10));
+            coverage.s.insert(i.to_string(), hits);
+        }
+        // istanbul ignore 的 statement 只计入 skipped
+        coverage.statement_map["3"].skip = true;
+        coverage.b.insert("0".into(), vec![2, 0]);
+        coverage.b.insert("1".into(), vec![0, 0, 1]);
+
+        let summary = CoverageSummary::from_file_coverage(&coverage);
+        assert_eq!(summary.statements, Totals::new(3, 1, 1));
+        assert_eq!(summary.lines, Totals::new(3, 1, 0));
+        assert_eq!(summary.branches, Totals::new(5, 2, 0));
+        assert_eq!(summary.functions, Totals::new(0, 0, 0));
+        assert_eq!(summary.get(Metric::Statements).pct, 33.33);
+        assert!(!summary.is_empty());
+        assert!(CoverageSummary::default().is_empty());
+    }
+
+    #[test]
+    fn skipped_items_are_left_out_of_totals() {
+        let json = r#"{
+            "path": "/app/a.js",
+            "statementMap": {
+                "0": { "start": { "line": 1, "column": 0 }, "end": { "line": 1, "column": 5 } },
+                "1": { "start": { "line": 2, "column": 0 }, "end": { "line": 2, "column": 5 }, "skip": true },
+                "2": { "start": { "line": 3, "column": 0 }, "end": { "line": 3, "column": 5 }, "skip": true }
+            },
+            "fnMap": {
+                "0": {
+                    "name": "f",
+                    "decl": { "start": { "line": 4, "column": 9 }, "end": { "line": 4, "column": 10 } },
+                    "loc": { "start": { "line": 4, "column": 13 }, "end": { "line": 4, "column": 15 }, "skip": true },
+                    "line": 4
+                }
+            },
+            "s": { "0": 0, "1": 0, "2": 3 },
+            "f": { "0": 0 }
+        }"#;
+        let coverage: FileCoverage = serde_json::from_str(json).unwrap();
+        let summary = CoverageSummary::from_file_coverage(&coverage);
+        assert_eq!(summary.statements, Totals::new(1, 0, 2));
+        assert_eq!(summary.statements.pct, 0.0);
+        assert_eq!(summary.functions, Totals::new(0, 0, 1));
+        assert_eq!(summary.functions.pct, 100.0);
+    }
+
+    #[test]
+    fn map_summary() {
+        let mut map = CoverageMap::new();
+        for (path, hits) in [("/app/a.js", 1), ("/app/b.js", 0)] {
+            let mut coverage = FileCoverage::new(path);
+            coverage.statement_map.insert("0".into(), Range::new(1, 0, 1, 10));
+            coverage.s.insert("0".into(), hits);
+            map.add_file_coverage(coverage);
+        }
+        assert_eq!(map.summary().statements, Totals::new(2, 1, 0));
+    }
+
+    #[test]
+    fn summary_by_file_and_directory() {
+        let mut map = CoverageMap::new();
+        for (path, hits) in [("/app/src/a.js", 1), ("/app/src/b.js", 0), ("/app/src/util/c.js", 1), ("d.js", 0)] {
+            let mut coverage = FileCoverage::new(path);
+            coverage.statement_map.insert("0".into(), Range::new(1, 0, 1, 10));
+            coverage.s.insert("0".into(), hits);
+            map.add_file_coverage(coverage);
+        }
+
+        let by_file = map.summary_by_file();
+        assert_eq!(by_file.len(), 4);
+        assert_eq!(by_file["/app/src/b.js"].statements.pct, 0.0);
+
+        // 只按文件所在目录聚合，子目录的文件不计入父目录
+        let by_dir = map.summary_by_directory();
+        assert_eq!(by_dir.keys().collect::<Vec<_>>(), ["", "/app/src", "/app/src/util"]);
+        assert_eq!(by_dir["/app/src"].statements, Totals::new(2, 1, 0));
+        assert_eq!(by_dir["/app/src/util"].statements, Totals::new(1, 1, 0));
+        assert_eq!(by_dir[""].statements, Totals::new(1, 0, 0));
+    }
+}
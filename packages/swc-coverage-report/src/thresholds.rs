@@ -0,0 +1,338 @@
+//! 覆盖率阈值检查，学 nyc 的 check-coverage：
+//! 正数表示最低百分比，负数表示允许的最大未覆盖数量
+
+use std::fmt;
+use std::path::Path;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use wax::{Glob, Pattern};
+
+use crate::coverage_map::CoverageMap;
+use crate::summary::{CoverageSummary, Metric, Totals};
+
+/// 四项指标的阈值，未设置的指标不检查
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Thresholds {
+    pub statements: Option<f64>,
+    pub branches: Option<f64>,
+    pub functions: Option<f64>,
+    pub lines: Option<f64>,
+}
+
+impl Thresholds {
+    pub fn get(&self, metric: Metric) -> Option<f64> {
+        match metric {
+            Metric::Lines => self.lines,
+            Metric::Statements => self.statements,
+            Metric::Functions => self.functions,
+            Metric::Branches => self.branches,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        Metric::ALL.iter().all(|m| self.get(*m).is_none())
+    }
+}
+
+/// 阈值配置：
+/// ```json
+/// { "lines": 80, "branches": -10, "perFile": { "lines": 50 }, "files": { "src/core/**": { "functions": 90 } } }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ThresholdConfig {
+    /// 对所有文件的汇总检查
+    #[serde(flatten)]
+    pub global: Thresholds,
+    /// 对每个文件单独检查
+    pub per_file: Option<Thresholds>,
+    /// glob -> 阈值，对匹配的每个文件单独检查
+    pub files: IndexMap<String, Thresholds>,
+    /// glob 匹配相对路径时使用的根目录，为空时匹配完整路径
+    #[serde(skip)]
+    pub root: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ThresholdScope {
+    Global,
+    File(String),
+    Glob { pattern: String, file: String },
+}
+
+/// 一条未达标记录
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThresholdViolation {
+    pub scope: ThresholdScope,
+    pub metric: Metric,
+    pub threshold: f64,
+    /// 百分比阈值时为实际百分比，未覆盖数阈值时为实际未覆盖数
+    pub actual: f64,
+}
+
+impl fmt::Display for ThresholdViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let metric = self.metric.name();
+        let target = match self.scope {
+            ThresholdScope::Global => "global threshold",
+            _ => "threshold",
+        };
+        if self.threshold < 0.0 {
+            write!(
+                f,
+                "ERROR: Uncovered count for {metric} ({}) exceeds {target} ({})",
+                self.actual, -self.threshold
+            )?;
+        } else {
+            write!(
+                f,
+                "ERROR: Coverage for {metric} ({}%) does not meet {target} ({}%)",
+                self.actual, self.threshold
+            )?;
+        }
+        match &self.scope {
+            ThresholdScope::Global => Ok(()),
+            ThresholdScope::File(file) => write!(f, " for {file}"),
+            ThresholdScope::Glob { pattern, file } => write!(f, " for {file} (matched by {pattern})"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ThresholdError {
+    InvalidGlob { pattern: String, message: String },
+}
+
+impl fmt::Display for ThresholdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThresholdError::InvalidGlob { pattern, message } => {
+                write!(f, "invalid threshold glob `{pattern}`: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ThresholdError {}
+
+/// 检查单项指标，未达标时返回实际值
+fn check_totals(totals: &Totals, threshold: f64) -> Option<f64> {
+    if threshold < 0.0 {
+        let uncovered = totals.total.saturating_sub(totals.covered) as f64;
+        (uncovered > -threshold).then_some(uncovered)
+    } else {
+        (totals.pct < threshold).then_some(totals.pct)
+    }
+}
+
+fn check_summary(
+    summary: &CoverageSummary,
+    thresholds: &Thresholds,
+    scope: &ThresholdScope,
+    violations: &mut Vec<ThresholdViolation>,
+) {
+    for metric in Metric::ALL {
+        let Some(threshold) = thresholds.get(metric) else {
+            continue;
+        };
+        if let Some(actual) = check_totals(summary.get(metric), threshold) {
+            violations.push(ThresholdViolation {
+                scope: scope.clone(),
+                metric,
+                threshold,
+                actual,
+            });
+        }
+    }
+}
+
+/// 按配置检查覆盖率，返回所有未达标项；为空表示全部通过
+pub fn check_coverage(
+    map: &CoverageMap,
+    config: &ThresholdConfig,
+) -> Result<Vec<ThresholdViolation>, ThresholdError> {
+    let globs = config
+        .files
+        .iter()
+        .map(|(pattern, thresholds)| {
+            Glob::new(pattern)
+                .map(|glob| (pattern, glob, thresholds))
+                .map_err(|e| ThresholdError::InvalidGlob {
+                    pattern: pattern.clone(),
+                    message: e.to_string(),
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut violations = vec![];
+    if !config.global.is_empty() {
+        check_summary(&map.summary(), &config.global, &ThresholdScope::Global, &mut violations);
+    }
+
+    for (path, summary) in map.summary_by_file() {
+        if let Some(per_file) = &config.per_file {
+            check_summary(&summary, per_file, &ThresholdScope::File(path.clone()), &mut violations);
+        }
+
+        // 按路径分段去掉根目录，`/app` 不会匹配到 `/app2/...`
+        let relative = config
+            .root
+            .as_deref()
+            .and_then(|root| Path::new(&path).strip_prefix(root).ok())
+            .and_then(Path::to_str)
+            .unwrap_or(&path);
+        for (pattern, glob, thresholds) in &globs {
+            if glob.is_match(relative) || glob.is_match(path.as_str()) {
+                let scope = ThresholdScope::Glob {
+                    pattern: (*pattern).clone(),
+                    file: path.clone(),
+                };
+                check_summary(&summary, thresholds, &scope, &mut violations);
+            }
+        }
+    }
+
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_coverage_instrument::Range;
+
+    use super::*;
+    use crate::coverage_map::FileCoverage;
+
+    /// 每个 statement 占一行，hits 为各 statement 的命中次数
+    fn file(path: &str, hits: &[u32]) -> FileCoverage {
+        let mut coverage = FileCoverage::new(path);
+        for (i, hit) in hits.iter().enumerate() {
+            let line = i as u32 + 1;
+            coverage.statement_map.insert(i.to_string(), Range::new(line, 0, line, 10));
+            coverage.s.insert(i.to_string(), *hit);
+        }
+        coverage
+    }
+
+    fn map(files: Vec<FileCoverage>) -> CoverageMap {
+        let mut map = CoverageMap::new();
+        for coverage in files {
+            map.add_file_coverage(coverage);
+        }
+        map
+    }
+
+    fn statements(threshold: f64) -> Thresholds {
+        Thresholds {
+            statements: Some(threshold),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn global_percentage() {
+        // 4 个 statement 覆盖 3 个：75%
+        let map = map(vec![file("/app/a.js", &[1, 1]), file("/app/b.js", &[1, 0])]);
+        let mut config = ThresholdConfig {
+            global: statements(75.0),
+            ..Default::default()
+        };
+        assert_eq!(check_coverage(&map, &config).unwrap(), vec![]);
+
+        config.global = statements(80.0);
+        let violations = check_coverage(&map, &config).unwrap();
+        assert_eq!(
+            violations,
+            vec![ThresholdViolation {
+                scope: ThresholdScope::Global,
+                metric: Metric::Statements,
+                threshold: 80.0,
+                actual: 75.0,
+            }]
+        );
+        assert_eq!(
+            violations[0].to_string(),
+            "ERROR: Coverage for statements (75%) does not meet global threshold (80%)"
+        );
+    }
+
+    #[test]
+    fn global_uncovered_count() {
+        let map = map(vec![file("/app/a.js", &[0, 0, 1])]);
+        let mut config = ThresholdConfig {
+            global: statements(-2.0),
+            ..Default::default()
+        };
+        assert_eq!(check_coverage(&map, &config).unwrap(), vec![]);
+
+        config.global = statements(-1.0);
+        let violations = check_coverage(&map, &config).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].actual, 2.0);
+        assert_eq!(
+            violations[0].to_string(),
+            "ERROR: Uncovered count for statements (2) exceeds global threshold (1)"
+        );
+    }
+
+    #[test]
+    fn per_file() {
+        let map = map(vec![file("/app/a.js", &[1, 1]), file("/app/b.js", &[1, 0])]);
+        let config = ThresholdConfig {
+            per_file: Some(statements(60.0)),
+            ..Default::default()
+        };
+        let violations = check_coverage(&map, &config).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].scope, ThresholdScope::File("/app/b.js".into()));
+        assert_eq!(violations[0].actual, 50.0);
+        assert!(violations[0].to_string().ends_with(" for /app/b.js"));
+    }
+
+    #[test]
+    fn glob_relative_to_root() {
+        let map = map(vec![
+            file("/app/lib/core/a.js", &[0]),
+            file("/app/lib/util/b.js", &[0]),
+            file("/app2/core/c.js", &[0]),
+        ]);
+        let mut config = ThresholdConfig {
+            root: Some("/app".into()),
+            ..Default::default()
+        };
+        config.files.insert("*/core/*.js".into(), statements(90.0));
+        let violations = check_coverage(&map, &config).unwrap();
+        let files: Vec<_> = violations
+            .iter()
+            .map(|v| match &v.scope {
+                ThresholdScope::Glob { file, .. } => file.as_str(),
+                scope => panic!("unexpected scope {scope:?}"),
+            })
+            .collect();
+        // /app2 不在根目录 /app 下，不能截成 `2/core/c.js` 去匹配
+        assert_eq!(files, vec!["/app/lib/core/a.js"]);
+    }
+
+    #[test]
+    fn invalid_glob() {
+        let mut config = ThresholdConfig::default();
+        config.files.insert("src/[".into(), statements(90.0));
+        assert!(matches!(
+            check_coverage(&CoverageMap::new(), &config),
+            Err(ThresholdError::InvalidGlob { .. })
+        ));
+    }
+
+    #[test]
+    fn config_from_json() {
+        let config: ThresholdConfig = serde_json::from_str(
+            r#"{ "lines": 80, "branches": -10, "perFile": { "lines": 50 }, "files": { "src/**": { "functions": 90 } } }"#,
+        )
+        .unwrap();
+        assert_eq!(config.global.lines, Some(80.0));
+        assert_eq!(config.global.branches, Some(-10.0));
+        assert_eq!(config.per_file.unwrap().lines, Some(50.0));
+        assert_eq!(config.files["src/**"].functions, Some(90.0));
+    }
+}
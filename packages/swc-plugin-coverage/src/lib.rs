@@ -1,19 +1,58 @@
-use swc_core::common::{SourceMapper, Span};
+use swc_core::common::{SourceMapper, Span, Spanned};
 use swc_core::ecma::visit::VisitMutWith;
 use swc_core::ecma::ast::Program;
 use swc_core::plugin::metadata::TransformPluginMetadataContextKind;
 use swc_core::plugin::{plugin_transform, proxies::TransformPluginProgramMetadata};
-use swc_coverage_instrument::{create_coverage_instrumentation_visitor, Range};
+use swc_coverage_instrument::{
+    create_coverage_instrumentation_visitor, create_coverage_instrumentation_visitor_with_comments, exceeded_file_limit,
+    parse_unified_diff, InstrumentOptions, Range,
+};
 
 #[plugin_transform]
 pub fn process_transform(mut program: Program, metadata: TransformPluginProgramMetadata) -> Program {
     let filename = metadata
         .get_context(&TransformPluginMetadataContextKind::Filename)
         .unwrap_or_else(|| "-".to_string());
-    let _cwd = metadata
+    let cwd = metadata
         .get_context(&TransformPluginMetadataContextKind::Cwd)
         .unwrap_or_else(|| ".".to_string());
 
+    let mut options: InstrumentOptions = metadata
+        .get_transform_plugin_config()
+        .map(|config| serde_json::from_str(&config).expect("Should able to deserialize plugin config"))
+        .unwrap_or_default();
+    let env_name = metadata.get_context(&TransformPluginMetadataContextKind::Env);
+    // 环境变量需在 .swcrc 的 jsc.experimental.pluginEnvVars 中声明，原生运行时直接读取进程环境
+    let lookup_env = |name: &str| {
+        metadata
+            .get_experimental_context(name)
+            .or_else(|| std::env::var(name).ok())
+    };
+    if !options.is_enabled(env_name.as_deref(), lookup_env) {
+        return program;
+    }
+
+    let source = || metadata.source_map.span_to_snippet(program.span()).ok();
+    if let Some(reason) = exceeded_file_limit(&options, &program, source) {
+        println!("swc-plugin-istanbul: 跳过 {filename}（{reason}）");
+        return program;
+    }
+
+    // 相对路径按 cwd 解析；wasm 运行时需要能访问该文件，读取失败时全量插桩
+    if let Some(diff_file) = &options.diff_file {
+        match std::fs::read_to_string(std::path::Path::new(&cwd).join(diff_file)) {
+            Ok(diff) => {
+                for (file, ranges) in parse_unified_diff(&diff) {
+                    options.changed_lines.entry(file).or_default().extend(ranges);
+                }
+            }
+            Err(e) => println!("swc-plugin-istanbul: 读取 diffFile {diff_file} 失败：{e}"),
+        }
+    }
+
+    let env_mapping = options.env_mapping.clone();
+    options.canyon.fill_from_env(&cwd, &env_mapping, lookup_env);
+
     let source_map = metadata.source_map.clone();
     let get_range = move |span: &Span| -> Range {
         if span.hi.is_dummy() || span.lo.is_dummy() {
@@ -29,7 +68,10 @@ pub fn process_transform(mut program: Program, metadata: TransformPluginProgramM
         )
     };
 
-    let mut visitor = create_coverage_instrumentation_visitor(&filename, get_range);
+    let mut visitor = match metadata.comments {
+        Some(comments) => create_coverage_instrumentation_visitor_with_comments(&filename, options, get_range, comments),
+        None => create_coverage_instrumentation_visitor(&filename, options, get_range),
+    };
     program.visit_mut_with(&mut visitor);
     program
 }
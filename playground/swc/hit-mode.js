@@ -0,0 +1,34 @@
+// 对比 hitMode: count / boolean 插桩 src/hot-loop.js 的结果：
+// boolean 模式的计数器写成 `= 1`，执行后命中次数最多为 1，覆盖与否和 count 模式一致
+const assert = require('assert')
+const path = require('path')
+const vm = require('vm')
+const { transformFileSync } = require('@swc/core')
+const wasmPath = path.resolve(__dirname, '..', '..', 'target', 'wasm32-wasip1', 'debug', 'swc_plugin_coverage.wasm')
+const file = path.join(__dirname, 'src', 'hot-loop.js')
+
+function run(hitMode) {
+  const { code } = transformFileSync(file, {
+    swcrc: false,
+    jsc: { experimental: { plugins: [[wasmPath, { hitMode }]] } },
+  })
+  const context = { console: { log() {} } }
+  vm.runInNewContext(code, context)
+  return { code, coverage: Object.values(context.__coverage__)[0] }
+}
+
+const count = run('count')
+const boolean = run('boolean')
+
+assert.match(count.code, /\.s\[\d+\]\+\+/)
+assert.doesNotMatch(boolean.code, /\+\+/)
+assert.match(boolean.code, /\.s\[\d+\] = 1/)
+
+const flags = (counts) => Object.values(counts).flat().map((hits) => Math.min(hits, 1))
+for (const key of ['s', 'f', 'b']) {
+  assert.ok(Object.values(boolean.coverage[key]).flat().every((hits) => hits <= 1), `${key} hits should be 0 or 1`)
+  assert.deepStrictEqual(flags(boolean.coverage[key]), flags(count.coverage[key]), `${key} hit/miss should match`)
+}
+// 循环体执行了 4 次
+assert.ok(Object.values(count.coverage.s).includes(4))
+console.log('hitMode fixtures ok')
@@ -0,0 +1,26 @@
+// 跳过生成代码的规则只在对应产物中生效：
+// 用户代码里的 _c / _s / _extends 照常计数，React Refresh 产物里的注册代码不计数
+const assert = require('assert')
+const path = require('path')
+const { transformFileSync } = require('@swc/core')
+const wasmPath = path.resolve(__dirname, '..', '..', 'target', 'wasm32-wasip1', 'debug', 'swc_plugin_coverage.wasm')
+
+function instrument(name) {
+  const { code } = transformFileSync(path.join(__dirname, 'src', name), {
+    swcrc: false,
+    jsc: { experimental: { plugins: [[wasmPath, {}]] } },
+  })
+  return code
+}
+
+const shortNames = instrument('short-names.js')
+assert.match(shortNames, /var _c = \(cov_\w+\(\)\.s\[\d+\]\+\+, 0\)/)
+assert.match(shortNames, /function _extends\(target, source\) \{\s*cov_\w+\(\)\.f\[\d+\]\+\+/)
+assert.match(shortNames, /s\[\d+\]\+\+;\s*_c = _c \+ 1;/)
+
+const refresh = instrument('react-refresh.js')
+assert.match(refresh, /^var _s = \$RefreshSig\$\(\);$/m)
+assert.match(refresh, /^_c = App;$/m)
+assert.match(refresh, /^\$RefreshReg\$\(_c, "App"\);$/m)
+assert.match(refresh, /function App\(\) \{\s*cov_\w+\(\)\.f\[\d+\]\+\+/)
+console.log('skip-list fixtures ok')
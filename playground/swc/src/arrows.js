@@ -0,0 +1,11 @@
+const double = (x) => x * 2;
+const toEntry = (key, value) => ({ key, value });
+
+class Counter {
+    count = 0;
+    increment() {
+        return [1, 2].map((step) => (this.count += step));
+    }
+}
+
+console.log(double(2), toEntry('a', 1), new Counter().increment());
@@ -0,0 +1,8 @@
+#!/usr/bin/env node
+"use strict";
+
+function greet(name) {
+    return "hello " + name;
+}
+
+console.log(greet("swc"));
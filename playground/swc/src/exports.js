@@ -0,0 +1,7 @@
+export const base = 10, scale = (n) => n * base;
+
+export function describe(n) {
+    return `value: ${scale(n)}`;
+}
+
+export default () => describe(1);
@@ -0,0 +1,9 @@
+function sum(values) {
+    let total = 0;
+    for (const value of values) {
+        total += value > 0 ? value : 0;
+    }
+    return total;
+}
+
+console.log(sum([1, -2, 3, 4]));
@@ -0,0 +1,23 @@
+class Counter {
+    static instances = 0;
+    static {
+        Counter.instances = 0;
+    }
+    count = 0;
+    #step = 1;
+    #bump() {
+        return (this.count += this.#step);
+    }
+    tick() {
+        Counter.instances ||= 1;
+        return this.#bump();
+    }
+}
+
+function withDefaults(options) {
+    options.retries ??= 3;
+    options.verbose &&= options.retries > 1;
+    return options.onDone?.(options) ?? options;
+}
+
+console.log(new Counter().tick(), withDefaults({ verbose: true }));
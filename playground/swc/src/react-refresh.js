@@ -0,0 +1,11 @@
+// React Refresh 转换后的代码：只有注册代码被跳过，组件本身照常计数
+var _s = $RefreshSig$();
+export function App() {
+    _s();
+    const [count] = useState(0);
+    return count;
+}
+_s(App, "useState{[count]}");
+_c = App;
+var _c;
+$RefreshReg$(_c, "App");
@@ -0,0 +1,15 @@
+// 用户代码中的 _c / _s / _extends 不是生成的代码，需要正常计数
+var _c = 0;
+var _s = createStore();
+
+function _extends(target, source) {
+    return Object.assign(target, source);
+}
+
+function createStore() {
+    return { count: 0 };
+}
+
+_c = _c + 1;
+_s.count = _c;
+console.log(_extends({}, _s));
@@ -0,0 +1,9 @@
+function run(list, flag) {
+    let total = 0;
+    for (const item of list) total += item;
+    while (total > 10) total -= 10;
+    if (flag) total++; else total--;
+    return total;
+}
+
+console.log(run([1, 2, 3], true));
@@ -0,0 +1,38 @@
+// 本地 HTTP 桩：用 upload 配置插桩 src/file.js，执行后手动触发一次上报，
+// 校验收到的 POST 中带有构建元数据、sessionId 和 coverage
+const http = require('http')
+const path = require('path')
+const vm = require('vm')
+const { transformFileSync } = require('@swc/core')
+const wasmPath = path.resolve(__dirname, '..', '..', 'target', 'wasm32-wasip1', 'debug', 'swc_plugin_coverage.wasm')
+
+const server = http.createServer((req, res) => {
+  let body = ''
+  req.on('data', (chunk) => (body += chunk))
+  req.on('end', () => {
+    res.end('ok')
+    const payload = JSON.parse(body)
+    const files = Object.keys(payload.coverage || {})
+    console.log(`${req.method} ${req.url} sha=${payload.sha} session=${payload.sessionId} files=${files.join(',')}`)
+    process.exit(payload.sha === 'stub-sha' && payload.sessionId && files.length > 0 ? 0 : 1)
+  })
+})
+
+server.listen(0, '127.0.0.1', () => {
+  const dsn = `http://127.0.0.1:${server.address().port}/coverage/client`
+  const { code } = transformFileSync(path.join(__dirname, 'src', 'file.js'), {
+    swcrc: false,
+    jsc: {
+      experimental: {
+        plugins: [[wasmPath, { dsn, sha: 'stub-sha', upload: { interval: 60000 } }]],
+      },
+    },
+  })
+  vm.runInThisContext(code)
+  globalThis.__canyon_upload__()
+})
+
+setTimeout(() => {
+  console.error('no coverage upload received')
+  process.exit(1)
+}, 5000)
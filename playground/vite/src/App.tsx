@@ -2,6 +2,12 @@ import { useState } from 'react'
 import reactLogo from './assets/react.svg'
 import viteLogo from '/vite.svg'
 import './App.css'
+import { TaskList, type Task } from './components/TaskList'
+
+const tasks: Task[] = [
+  { id: 1, title: 'Instrument TSX', done: true },
+  { id: 2, title: 'Collect coverage', done: false },
+]
 
 function App() {
   const [count, setCount] = useState(0)
@@ -25,6 +31,7 @@ function App() {
           Edit <code>src/App.tsx</code> and save to test HMR
         </p>
       </div>
+      <TaskList loading={count % 2 === 1} tasks={tasks} filter={count > 2 ? 'done' : 'all'} />
       <p className="read-the-docs">
         Click on the Vite and React logos to learn more
       </p>
@@ -0,0 +1,32 @@
+export type Task = { id: number; title: string; done: boolean }
+
+type Props = {
+  loading: boolean
+  tasks: Task[]
+  filter?: 'all' | 'done'
+}
+
+export function TaskList({ loading, tasks, filter = 'all' }: Props) {
+  const visible = filter === 'done' ? tasks.filter((task) => task.done) : tasks
+
+  return (
+    <section className={loading ? 'tasks loading' : 'tasks'} aria-busy={loading || undefined}>
+      {loading ? (
+        <p>Loading…</p>
+      ) : (
+        <>
+          {visible.length > 0 && (
+            <ul>
+              {visible.map((task) => (
+                <li key={task.id} className={task.done ? 'done' : undefined}>
+                  {task.title}
+                </li>
+              ))}
+            </ul>
+          )}
+          {visible.length === 0 && <p>No tasks</p>}
+        </>
+      )}
+    </section>
+  )
+}
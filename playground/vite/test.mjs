@@ -0,0 +1,63 @@
+// TSX fixture：经 @vitejs/plugin-react-swc（vite.config.ts 中配置了插件）构建 SSR 产物，
+// 用 react-dom/server 渲染几种状态，检查 JSX 中的分支覆盖率
+import assert from 'node:assert/strict'
+import path from 'node:path'
+import { fileURLToPath, pathToFileURL } from 'node:url'
+import { build } from 'vite'
+import { renderToStaticMarkup } from 'react-dom/server'
+import { createElement } from 'react'
+
+const __dirname = path.dirname(fileURLToPath(import.meta.url))
+const outDir = path.join(__dirname, 'dist-fixture')
+const fixture = path.join(__dirname, 'src', 'components', 'TaskList.tsx')
+
+await build({
+  root: __dirname,
+  logLevel: 'warn',
+  build: {
+    ssr: fixture,
+    outDir,
+    emptyOutDir: true,
+    rollupOptions: { output: { entryFileNames: 'TaskList.mjs' } },
+  },
+})
+
+const { TaskList } = await import(pathToFileURL(path.join(outDir, 'TaskList.mjs')).href)
+const tasks = [
+  { id: 1, title: 'write fixture', done: true },
+  { id: 2, title: 'ship it', done: false },
+]
+const render = (props) => renderToStaticMarkup(createElement(TaskList, props))
+
+assert.match(render({ loading: true, tasks }), /Loading/)
+assert.match(render({ loading: false, tasks, filter: 'done' }), /<li class="done">write fixture<\/li>/)
+assert.match(render({ loading: false, tasks: [] }), /No tasks/)
+
+const coverage = Object.values(globalThis.__coverage__ ?? {}).find((file) => file.path.endsWith('TaskList.tsx'))
+assert.ok(coverage, 'TaskList.tsx should be instrumented')
+
+const branches = Object.entries(coverage.branchMap).map(([id, branch]) => ({
+  type: branch.type,
+  line: branch.loc.start.line,
+  hits: coverage.b[id],
+}))
+const at = (type, line) => {
+  const branch = branches.find((b) => b.type === type && b.line === line)
+  assert.ok(branch, `missing ${type} branch on line ${line}`)
+  return branch.hits
+}
+
+// 属性值中的三元表达式和逻辑表达式
+assert.deepEqual(at('cond-expr', 13), [1, 2])
+assert.deepEqual(at('binary-expr', 13), [3, 2])
+// 子节点中的 loading ? <p/> : <>...</>
+assert.deepEqual(at('cond-expr', 14), [1, 2])
+// fragment 子节点中的 &&
+assert.deepEqual(at('binary-expr', 18), [2, 1])
+assert.deepEqual(at('binary-expr', 27), [2, 1])
+// 列表项属性中的三元表达式
+assert.deepEqual(at('cond-expr', 21), [1, 0])
+// 解构参数默认值
+assert.deepEqual(at('default-arg', 9), [2])
+
+console.log(`ok: ${branches.length} branches in TaskList.tsx`)